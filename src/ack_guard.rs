@@ -0,0 +1,81 @@
+use std::ops::{Deref, DerefMut};
+use std::time;
+
+use crate::queue::*;
+
+/// An item taken with `get_ack`. Unless `ack` is called, the item is returned to the
+/// queue when the guard is dropped, as the next item to be taken. The requeue ignores the
+/// bound, so a queue filled up in the meantime briefly holds one item too many.
+pub struct AckGuard<Q: BasicArray<T>, T> {
+    queue: BaseQueue<Q, T>,
+    value: Option<T>,
+}
+
+impl<Q: BasicArray<T>, T> AckGuard<Q, T> {
+    /// Acknowledges the item, removing it from the queue for good.
+    pub fn ack(mut self) -> T {
+        self.value.take().unwrap()
+    }
+}
+
+impl<Q: BasicArray<T>, T> Deref for AckGuard<Q, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<Q: BasicArray<T>, T> DerefMut for AckGuard<Q, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().unwrap()
+    }
+}
+
+impl<Q: BasicArray<T>, T> Drop for AckGuard<Q, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            let mut queue = self.queue.inner.queue.lock().unwrap();
+            queue.requeue(value);
+            self.queue.inner.record_len(queue.len());
+            drop(queue);
+            self.queue.inner.notify_not_empty();
+        }
+    }
+}
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Takes an item like `get_wait`, for at-least-once processing. The item is requeued
+    /// if the returned guard is dropped without being acknowledged. A requeued item is
+    /// put back even if the queue has been filled up in the meantime.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// queue.put(1).unwrap();
+    /// queue.put(2).unwrap();
+    ///
+    /// let item = queue.get_ack(time::Duration::from_millis(100)).unwrap();
+    /// assert_eq!(*item, 1);
+    /// assert_eq!(item.ack(), 1);
+    /// assert_eq!(queue.len(), 1);
+    ///
+    /// let item = queue.get_ack(time::Duration::from_millis(100)).unwrap();
+    /// assert_eq!(*item, 2);
+    /// drop(item);
+    /// assert_eq!(queue.len(), 1);
+    ///
+    /// queue.put(3).unwrap();
+    /// assert_eq!(queue.get().unwrap(), 2);
+    /// ```
+    pub fn get_ack(&self, timeout: time::Duration) -> Result<AckGuard<Q, T>, QueueError> {
+        let value = self.get_wait(timeout)?;
+        Ok(AckGuard {
+            queue: self.clone(),
+            value: Some(value),
+        })
+    }
+}
@@ -0,0 +1,157 @@
+use std::cmp::{Ord, Ordering};
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use std::time;
+
+use crate::priority_queue::PrioritizedItem;
+use crate::queue::*;
+
+struct AgedItem<T, P> {
+    key: f64,
+    item: PrioritizedItem<T, P>,
+}
+
+impl<T, P> Eq for AgedItem<T, P> {}
+
+impl<T, P> PartialEq<Self> for AgedItem<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key.total_cmp(&other.key).is_eq()
+    }
+}
+
+impl<T, P> PartialOrd<Self> for AgedItem<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, P> Ord for AgedItem<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.total_cmp(&other.key)
+    }
+}
+
+/// A heap whose items gain `rate` priority per second spent queued.
+///
+/// Every item ages at the same rate, so the effective priority `p + rate * (now - t)`
+/// orders items exactly like the fixed key `p - rate * t`, and the heap never needs to be
+/// rebuilt as time passes.
+pub struct AgingHeap<T, P> {
+    heap: BinaryHeap<AgedItem<T, P>>,
+    rate: f64,
+    epoch: time::Instant,
+    /// The key of the last item taken, which a requeue gives back to it.
+    taken: Option<f64>,
+}
+
+impl<T, P> AgingHeap<T, P> {
+    fn with_rate(maxsize: Option<usize>, rate: f64) -> Self {
+        Self {
+            heap: match maxsize {
+                None => BinaryHeap::new(),
+                Some(s) => BinaryHeap::with_capacity(s),
+            },
+            rate,
+            epoch: time::Instant::now(),
+            taken: None,
+        }
+    }
+}
+
+impl<T, P: Copy + Into<f64>> BasicArray<PrioritizedItem<T, P>> for AgingHeap<T, P> {
+    fn new(maxsize: Option<usize>) -> Self {
+        Self::with_rate(maxsize, 0.0)
+    }
+
+    fn new_empty(&self, maxsize: Option<usize>) -> Self {
+        Self::with_rate(maxsize, self.rate)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn get(&mut self) -> Option<PrioritizedItem<T, P>> {
+        let aged = self.heap.pop()?;
+        self.taken = Some(aged.key);
+        Some(aged.item)
+    }
+
+    fn put(&mut self, value: PrioritizedItem<T, P>) {
+        let age = self.epoch.elapsed().as_secs_f64();
+        self.heap.push(AgedItem {
+            key: value.1.into() - self.rate * age,
+            item: value,
+        })
+    }
+
+    /// The requeued item gets back the key of the last item taken, so it keeps the age it
+    /// had, assuming it is that item.
+    fn requeue(&mut self, value: PrioritizedItem<T, P>) {
+        match self.taken.take() {
+            Some(key) => self.heap.push(AgedItem { key, item: value }),
+            None => self.put(value),
+        }
+    }
+
+    fn peek(&self) -> Option<&PrioritizedItem<T, P>> {
+        self.heap.peek().map(|aged| &aged.item)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &PrioritizedItem<T, P>> + '_> {
+        Box::new(self.heap.iter().map(|aged| &aged.item))
+    }
+}
+
+impl<T, P: Copy + Into<f64>> AgingPriorityQueue<T, P> {
+    /// Creates a queue where items gain `rate` priority per second they spend queued.
+    pub fn with_aging_rate(maxsize: Option<usize>, rate: f64) -> Self {
+        Self {
+            inner: Arc::new(QueueInner::with_array(
+                AgingHeap::with_rate(maxsize, rate),
+                maxsize,
+            )),
+        }
+    }
+}
+
+/// Queue with a priority which increases the longer an item waits, so that low priority
+/// items are not starved by a constant stream of higher priority ones.
+///
+/// # Example
+/// ```
+/// use std::thread;
+/// use std::time;
+/// use rueue::{AgingPriorityQueue, PrioritizedItem, Queue};
+///
+/// let queue = AgingPriorityQueue::with_aging_rate(None, 1000.0);
+///
+/// queue.put(PrioritizedItem("old", 0)).unwrap();
+/// thread::sleep(time::Duration::from_millis(50));
+/// queue.put(PrioritizedItem("new", 10)).unwrap();
+/// queue.put(PrioritizedItem("newer", 20)).unwrap();
+///
+/// assert_eq!(queue.get().unwrap().0, "old");
+/// assert_eq!(queue.get().unwrap().0, "newer");
+/// assert_eq!(queue.get().unwrap().0, "new");
+/// ```
+///
+/// An item given back with `get_ack` keeps the age it had when it was taken.
+/// ```
+/// use std::thread;
+/// use std::time;
+/// use rueue::{AgingPriorityQueue, PrioritizedItem, Queue};
+///
+/// let queue = AgingPriorityQueue::with_aging_rate(None, 1000.0);
+///
+/// queue.put(PrioritizedItem("old", 0)).unwrap();
+/// thread::sleep(time::Duration::from_millis(50));
+/// queue.put(PrioritizedItem("new", 10)).unwrap();
+///
+/// let guard = queue.get_ack(time::Duration::ZERO).unwrap();
+/// assert_eq!(guard.0, "old");
+/// thread::sleep(time::Duration::from_millis(50));
+/// drop(guard);
+/// assert_eq!(queue.get().unwrap().0, "old");
+/// ```
+pub type AgingPriorityQueue<T, P> = BaseQueue<AgingHeap<T, P>, PrioritizedItem<T, P>>;
@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time;
+
+use crate::queue::*;
+
+/// How many of the most recent operations a queue keeps in its audit log.
+const AUDIT_CAPACITY: usize = 64;
+
+/// A queue operation recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOp {
+    /// An item was put.
+    Put,
+    /// An item was taken.
+    Get,
+    /// A put was rejected with the given error.
+    Reject(QueueError),
+}
+
+/// An entry of the audit log: the operation, when it happened, and the queue length
+/// right after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub op: AuditOp,
+    pub at: time::Instant,
+    pub len: usize,
+}
+
+/// The last `AUDIT_CAPACITY` operations on a queue, oldest first.
+pub(crate) struct AuditRing {
+    entries: Mutex<VecDeque<AuditEntry>>,
+}
+
+impl AuditRing {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(AUDIT_CAPACITY)),
+        }
+    }
+
+    pub(crate) fn record(&self, op: AuditOp, len: usize) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= AUDIT_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(AuditEntry {
+            op,
+            at: time::Instant::now(),
+            len,
+        });
+    }
+}
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Returns the last 64 puts, gets and rejected puts, oldest first. Operations which
+    /// bypass `put` and `get`, like batch gets or drains, are not recorded. Only available
+    /// with the `audit` feature.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{AuditOp, FifoQueue, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(Some(1));
+    /// queue.put(1).unwrap();
+    /// assert!(queue.put(2).is_err());
+    /// queue.get().unwrap();
+    ///
+    /// let log: Vec<_> = queue.audit_log().iter().map(|entry| (entry.op, entry.len)).collect();
+    /// assert_eq!(
+    ///     log,
+    ///     vec![(AuditOp::Put, 1), (AuditOp::Reject(QueueError::Full), 1), (AuditOp::Get, 0)]
+    /// );
+    ///
+    /// for i in 0..100 {
+    ///     queue.put(i).unwrap();
+    ///     queue.get().unwrap();
+    /// }
+    /// let log = queue.audit_log();
+    /// assert_eq!(log.len(), 64);
+    /// assert!(log.windows(2).all(|pair| pair[0].at <= pair[1].at));
+    /// assert_eq!(log.last().unwrap().op, AuditOp::Get);
+    /// ```
+    pub fn audit_log(&self) -> Vec<AuditEntry> {
+        self.inner
+            .audit
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect()
+    }
+}
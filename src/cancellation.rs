@@ -0,0 +1,242 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time;
+
+use crate::queue::*;
+
+/// Something blocked threads wait on, which a token wakes when it is cancelled.
+pub(crate) trait Wake: Send + Sync {
+    fn wake(&self);
+}
+
+impl<Q: BasicArray<T> + Send, T: Send + Sync> Wake for QueueInner<Q, T> {
+    fn wake(&self) {
+        let _pending = self.pending.lock().unwrap();
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+}
+
+struct TokenInner {
+    cancelled: AtomicBool,
+    waiters: Mutex<Vec<Weak<dyn Wake>>>,
+}
+
+/// A cancellation shared by any number of waits on any number of queues. Clones refer to
+/// the same token, so one `cancel()` interrupts every wait using it.
+///
+/// Waits register their queue with the token, and `cancel()` notifies the queues of all
+/// registered waits, so a blocked wait returns `QueueError::Cancelled` right away rather
+/// than at its next timeout.
+#[derive(Clone)]
+pub struct CancellationToken {
+    inner: Arc<TokenInner>,
+}
+
+/// Keeps a queue registered with a token for as long as a wait on it is in progress.
+struct Registration<'a> {
+    token: &'a CancellationToken,
+    waiter: Weak<dyn Wake>,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        let mut waiters = self.token.inner.waiters.lock().unwrap();
+        let position = waiters.iter().position(|w| Weak::ptr_eq(w, &self.waiter));
+        if let Some(i) = position {
+            waiters.swap_remove(i);
+        }
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(TokenInner {
+                cancelled: AtomicBool::new(false),
+                waiters: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Cancels the token, waking every wait using it. Cancelling twice has no further effect.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        let waiters: Vec<_> = self.inner.waiters.lock().unwrap().clone();
+        for waiter in waiters.iter().filter_map(Weak::upgrade) {
+            waiter.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Registers `waiter` before the caller first checks `is_cancelled`, so a `cancel()`
+    /// after that check is sure to notify it.
+    fn register(&self, waiter: Arc<dyn Wake>) -> Registration<'_> {
+        let waiter = Arc::downgrade(&waiter);
+        self.inner
+            .waiters
+            .lock()
+            .unwrap()
+            .push(Weak::clone(&waiter));
+        Registration {
+            token: self,
+            waiter,
+        }
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Q: BasicArray<T> + Send + 'static, T: Send + Sync + 'static> BaseQueue<Q, T> {
+    /// Like `get_wait`, but gives up with `QueueError::Cancelled` as soon as `token` is
+    /// cancelled. An already cancelled token fails the call without taking an item.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{CancellationToken, FifoQueue, LifoQueue, QueueError};
+    ///
+    /// let fifo: FifoQueue<i32> = FifoQueue::new(None);
+    /// let lifo: LifoQueue<i32> = LifoQueue::new(None);
+    /// let token = CancellationToken::new();
+    ///
+    /// let mut consumers = Vec::new();
+    /// for _ in 0..2 {
+    ///     let (queue, cancel) = (fifo.clone(), token.clone());
+    ///     consumers.push(thread::spawn(move || queue.get_wait_with_token(time::Duration::ZERO, &cancel)));
+    ///     let (queue, cancel) = (lifo.clone(), token.clone());
+    ///     consumers.push(thread::spawn(move || queue.get_wait_with_token(time::Duration::ZERO, &cancel)));
+    /// }
+    /// while fifo.waiting_consumers() + lifo.waiting_consumers() < 4 {
+    ///     thread::sleep(time::Duration::from_millis(1));
+    /// }
+    ///
+    /// let timestamp = time::Instant::now();
+    /// token.cancel();
+    /// for consumer in consumers {
+    ///     assert_eq!(consumer.join().unwrap(), Err(QueueError::Cancelled));
+    /// }
+    /// assert!(timestamp.elapsed() < time::Duration::from_millis(1000));
+    /// assert!(token.is_cancelled());
+    /// ```
+    pub fn get_wait_with_token(
+        &self,
+        timeout: time::Duration,
+        token: &CancellationToken,
+    ) -> Result<T, QueueError> {
+        let _registration = token.register(self.inner.clone());
+        let timestamp = time::Instant::now();
+        loop {
+            if token.is_cancelled() {
+                return Err(QueueError::Cancelled);
+            }
+            if self.inner.is_shut_down() {
+                return Err(QueueError::ShutDown);
+            }
+            match self.get() {
+                Err(QueueError::Empty) => {}
+                ret => return ret,
+            }
+            let remaining = match timeout.is_zero() {
+                true => time::Duration::ZERO,
+                false => match timeout.checked_sub(timestamp.elapsed()) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    _ => return Err(QueueError::Empty),
+                },
+            };
+            let _waiting = Waiting::new(&self.inner.waiting_consumers);
+            self.inner.wait_until(
+                &self.inner.not_empty,
+                || !self.is_empty() || self.inner.is_closed() || token.is_cancelled(),
+                remaining,
+            );
+        }
+    }
+
+    /// Like `put_wait`, but gives up with `QueueError::Cancelled` as soon as `token` is
+    /// cancelled, handing back the value.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{CancellationToken, FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(Some(1));
+    /// queue.put(0).unwrap();
+    /// let token = CancellationToken::new();
+    ///
+    /// let (producer, cancel) = (queue.clone(), token.clone());
+    /// let th = thread::spawn(move || producer.put_wait_with_token(1, time::Duration::ZERO, &cancel));
+    /// while queue.waiting_producers() < 1 {
+    ///     thread::sleep(time::Duration::from_millis(1));
+    /// }
+    ///
+    /// token.cancel();
+    /// assert_eq!(th.join().unwrap(), Err(PutError(1, QueueError::Cancelled)));
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    pub fn put_wait_with_token(
+        &self,
+        mut value: T,
+        timeout: time::Duration,
+        token: &CancellationToken,
+    ) -> Result<(), PutError<T>> {
+        let _registration = token.register(self.inner.clone());
+        let timestamp = time::Instant::now();
+        loop {
+            if token.is_cancelled() {
+                return Err(PutError(value, QueueError::Cancelled));
+            }
+            if self.inner.is_shut_down() {
+                return Err(PutError(value, QueueError::ShutDown));
+            }
+            let err = match self.insert(value) {
+                Err(PutError(rejected, err @ (QueueError::Full | QueueError::RateLimited))) => {
+                    value = rejected;
+                    err
+                }
+                ret => return ret.map(|_| ()),
+            };
+            let remaining = match timeout.is_zero() {
+                true => time::Duration::ZERO,
+                false => match timeout.checked_sub(timestamp.elapsed()) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    _ => return Err(PutError(value, err)),
+                },
+            };
+            if err == QueueError::RateLimited {
+                // Sleep on the condvar rather than `thread::sleep`, so a cancel still wakes us.
+                let delay = match &self.inner.rate_limit {
+                    Some(bucket) => bucket.lock().unwrap().time_until_token(),
+                    None => time::Duration::ZERO,
+                };
+                if !timeout.is_zero() && delay > remaining {
+                    return Err(PutError(value, err));
+                }
+                if delay.is_zero() {
+                    thread::yield_now();
+                } else {
+                    self.inner
+                        .wait_until(&self.inner.not_full, || token.is_cancelled(), delay);
+                }
+                continue;
+            }
+            let _waiting = Waiting::new(&self.inner.waiting_producers);
+            self.inner.wait_until(
+                &self.inner.not_full,
+                || !self.is_full() || self.inner.is_closed() || token.is_cancelled(),
+                remaining,
+            );
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::priority_queue::PrioritizedItem;
+use crate::queue::*;
+
+/// A heap holding at most `cap` items of any single priority.
+///
+/// Counts are kept per priority value and dropped once a priority has no items left, so
+/// the map only grows with the number of distinct priorities currently queued.
+pub struct CappedHeap<T, P> {
+    heap: BinaryHeap<PrioritizedItem<T, P>>,
+    counts: HashMap<P, usize>,
+    cap: usize,
+}
+
+impl<T, P> CappedHeap<T, P> {
+    fn with_cap(maxsize: Option<usize>, cap: usize) -> Self {
+        Self {
+            heap: match maxsize {
+                None => BinaryHeap::new(),
+                Some(s) => BinaryHeap::with_capacity(s),
+            },
+            counts: HashMap::new(),
+            cap,
+        }
+    }
+}
+
+impl<T, P: Ord + Hash + Clone> BasicArray<PrioritizedItem<T, P>> for CappedHeap<T, P> {
+    fn new(maxsize: Option<usize>) -> Self {
+        Self::with_cap(maxsize, usize::MAX)
+    }
+
+    fn new_empty(&self, maxsize: Option<usize>) -> Self {
+        Self::with_cap(maxsize, self.cap)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn get(&mut self) -> Option<PrioritizedItem<T, P>> {
+        let value = self.heap.pop()?;
+        if let Some(count) = self.counts.get_mut(&value.1) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&value.1);
+            }
+        }
+        Some(value)
+    }
+
+    fn put(&mut self, value: PrioritizedItem<T, P>) {
+        *self.counts.entry(value.1.clone()).or_insert(0) += 1;
+        self.heap.push(value);
+    }
+
+    fn peek(&self) -> Option<&PrioritizedItem<T, P>> {
+        self.heap.peek()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &PrioritizedItem<T, P>> + '_> {
+        Box::new(self.heap.iter())
+    }
+
+    fn admits(&self, value: &PrioritizedItem<T, P>) -> bool {
+        self.counts.get(&value.1).copied().unwrap_or(0) < self.cap
+    }
+}
+
+impl<T, P: Ord + Hash + Clone> CappedPriorityQueue<T, P> {
+    /// Creates a queue holding at most `cap` items of any single priority. Puts over the
+    /// cap fail with `QueueError::PriorityCap`, without waiting in `put_wait`, and stop
+    /// `put_from_iter` and `transfer`.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{CappedPriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// let queue = CappedPriorityQueue::with_priority_cap(None, 2);
+    /// let items = [("a", 1), ("b", 1), ("c", 1), ("d", 2)].map(|(v, p)| PrioritizedItem(v, p));
+    /// let mut leftover = queue.put_from_iter(items);
+    /// assert_eq!(queue.len(), 2);
+    /// assert_eq!(leftover.next().unwrap().0, "c");
+    ///
+    /// let src = CappedPriorityQueue::with_priority_cap(None, 2);
+    /// src.put(PrioritizedItem("e", 1)).unwrap();
+    /// assert_eq!(src.transfer(&queue, 1), 0);
+    /// assert_eq!(src.len(), 1);
+    /// assert_eq!(queue.len(), 2);
+    /// ```
+    pub fn with_priority_cap(maxsize: Option<usize>, cap: usize) -> Self {
+        Self {
+            inner: Arc::new(QueueInner::with_array(
+                CappedHeap::with_cap(maxsize, cap),
+                maxsize,
+            )),
+        }
+    }
+}
+
+/// Queue with a priority, which limits how many items of one priority can be queued so
+/// that a single busy priority can't take up the whole queue.
+///
+/// # Example
+/// ```
+/// use rueue::{CappedPriorityQueue, PrioritizedItem, PutError, Queue, QueueError};
+///
+/// let queue = CappedPriorityQueue::with_priority_cap(None, 2);
+///
+/// queue.put(PrioritizedItem("a", 1)).unwrap();
+/// queue.put(PrioritizedItem("b", 1)).unwrap();
+/// assert!(matches!(
+///     queue.put(PrioritizedItem("c", 1)),
+///     Err(PutError(PrioritizedItem("c", 1), QueueError::PriorityCap))
+/// ));
+///
+/// queue.put(PrioritizedItem("d", 2)).unwrap();
+/// queue.put(PrioritizedItem("e", 3)).unwrap();
+/// assert_eq!(queue.len(), 4);
+///
+/// assert_eq!(queue.get().unwrap().0, "e");
+/// assert_eq!(queue.get().unwrap().0, "d");
+/// assert_eq!(queue.get().unwrap().1, 1);
+/// queue.put(PrioritizedItem("c", 1)).unwrap();
+/// ```
+pub type CappedPriorityQueue<T, P> = BaseQueue<CappedHeap<T, P>, PrioritizedItem<T, P>>;
@@ -0,0 +1,39 @@
+use crate::queue::*;
+
+/// Closes its queue when dropped, tying the queue's lifetime to a scope.
+pub struct CloseGuard<Q: BasicArray<T>, T> {
+    queue: BaseQueue<Q, T>,
+}
+
+impl<Q: BasicArray<T>, T> Drop for CloseGuard<Q, T> {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Creates a queue along with a guard which closes it when dropped.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue, QueueError};
+    ///
+    /// let th = {
+    ///     let (queue, _guard) = FifoQueue::<i32>::scoped(None);
+    ///     let consumer = queue.clone();
+    ///     let th = thread::spawn(move || consumer.get_wait(time::Duration::ZERO));
+    ///     thread::sleep(time::Duration::from_millis(10));
+    ///     th
+    /// };
+    /// assert_eq!(th.join().unwrap(), Err(QueueError::Closed));
+    /// ```
+    pub fn scoped(maxsize: Option<usize>) -> (Self, CloseGuard<Q, T>) {
+        let queue = Self::new(maxsize);
+        let guard = CloseGuard {
+            queue: queue.clone(),
+        };
+        (queue, guard)
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::{BinaryHeap, HashSet};
+use std::hash::Hash;
+
+use crate::priority_queue::PrioritizedItem;
+use crate::queue::*;
+
+/// An item which identifies itself by a key, so that a `DedupQueue` can tell whether an
+/// equivalent item is already queued.
+pub trait Keyed<K> {
+    fn key(&self) -> K;
+}
+
+/// A prioritized item is keyed by its value.
+impl<T: Keyed<K>, P, K> Keyed<K> for PrioritizedItem<T, P> {
+    fn key(&self) -> K {
+        self.0.key()
+    }
+}
+
+/// A heap which holds at most one item per key, dropping puts of keys it already holds.
+pub struct DedupHeap<T, K> {
+    heap: BinaryHeap<T>,
+    keys: HashSet<K>,
+}
+
+impl<T: Ord + Keyed<K>, K: Hash + Eq> BasicArray<T> for DedupHeap<T, K> {
+    fn new(maxsize: Option<usize>) -> Self {
+        match maxsize {
+            None => Self {
+                heap: BinaryHeap::new(),
+                keys: HashSet::new(),
+            },
+            Some(s) => Self {
+                heap: BinaryHeap::with_capacity(s),
+                keys: HashSet::with_capacity(s),
+            },
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn get(&mut self) -> Option<T> {
+        let value = self.heap.pop()?;
+        self.keys.remove(&value.key());
+        Some(value)
+    }
+
+    fn put(&mut self, value: T) {
+        if self.keys.insert(value.key()) {
+            self.heap.push(value);
+        }
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.heap.peek()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(self.heap.iter())
+    }
+
+    fn is_duplicate(&self, value: &T) -> bool {
+        self.keys.contains(&value.key())
+    }
+}
+
+impl<T: Ord + Keyed<K>, K: Hash + Eq> DedupQueue<T, K> {
+    /// Puts `value` like `put`, and reports `PutOutcome::Duplicate` if it was dropped
+    /// because an item with the same key is already queued.
+    pub fn put_unique(&self, value: T) -> Result<PutOutcome, PutError<T>> {
+        match self.insert(value)? {
+            Some(_) => Ok(PutOutcome::Ok),
+            None => Ok(PutOutcome::Duplicate),
+        }
+    }
+}
+
+/// Queue with a priority, which ignores items whose key is already queued. Once an item
+/// is taken, its key may be queued again.
+///
+/// # Example
+/// ```
+/// use rueue::{DedupQueue, Keyed, PrioritizedItem, PutOutcome, Queue};
+///
+/// #[derive(Debug)]
+/// struct Url(&'static str);
+///
+/// impl Keyed<&'static str> for Url {
+///     fn key(&self) -> &'static str {
+///         self.0
+///     }
+/// }
+///
+/// let frontier: DedupQueue<PrioritizedItem<Url, i32>, &str> = DedupQueue::new(None);
+///
+/// let put = |url, depth: i32| frontier.put_unique(PrioritizedItem(Url(url), -depth)).unwrap();
+/// assert_eq!(put("a.example", 0), PutOutcome::Ok);
+/// assert_eq!(put("b.example", 1), PutOutcome::Ok);
+/// assert_eq!(put("a.example", 2), PutOutcome::Duplicate);
+/// assert_eq!(frontier.len(), 2);
+///
+/// let item = frontier.get().unwrap();
+/// assert_eq!((item.0 .0, item.1), ("a.example", 0));
+/// assert_eq!(put("a.example", 2), PutOutcome::Ok);
+/// frontier.put(PrioritizedItem(Url("b.example"), 0)).unwrap();
+///
+/// assert_eq!(frontier.get().unwrap().0 .0, "b.example");
+/// assert_eq!(frontier.get().unwrap().0 .0, "a.example");
+/// assert!(frontier.is_empty());
+///
+/// // Batch puts and transfers drop duplicates too.
+/// frontier.put_from_iter(["c.example", "c.example"].map(|url| PrioritizedItem(Url(url), 0)));
+/// let other = DedupQueue::new(None);
+/// other.put(PrioritizedItem(Url("c.example"), 0)).unwrap();
+/// assert_eq!(other.transfer(&frontier, 1), 1);
+/// assert_eq!(frontier.len(), 1);
+/// assert!(other.is_empty());
+/// ```
+pub type DedupQueue<T, K> = BaseQueue<DedupHeap<T, K>, T>;
@@ -0,0 +1,43 @@
+use crate::queue::*;
+
+/// Puts `items` into `queues` round-robin, passing over queues which reject an item, like
+/// full ones, and returns the items no queue accepted, in their original order.
+///
+/// # Example
+/// ```
+/// use rueue::{distribute, FifoQueue, Queue};
+///
+/// let a = FifoQueue::new(None);
+/// let b = FifoQueue::new(Some(1));
+/// let c = FifoQueue::new(Some(3));
+/// b.put(-1).unwrap();
+///
+/// let leftover = distribute(&[&a, &b, &c], (0..10).collect());
+/// assert!(leftover.is_empty());
+/// assert_eq!(a.drain_sorted_by_key(|i| *i), vec![0, 2, 4, 6, 7, 8, 9]);
+/// assert_eq!(b.drain_sorted_by_key(|i| *i), vec![-1]);
+/// assert_eq!(c.drain_sorted_by_key(|i| *i), vec![1, 3, 5]);
+///
+/// let leftover = distribute(&[&b, &c], (0..10).collect());
+/// assert_eq!(leftover, (4..10).collect::<Vec<_>>());
+/// assert!(b.is_full() && c.is_full());
+/// ```
+pub fn distribute<T>(queues: &[&dyn Queue<T>], items: Vec<T>) -> Vec<T> {
+    let mut leftover = Vec::new();
+    let mut next = 0;
+    for value in items {
+        let mut value = Some(value);
+        for offset in 0..queues.len() {
+            let i = (next + offset) % queues.len();
+            match queues[i].put(value.take().unwrap()) {
+                Ok(()) => {
+                    next = i + 1;
+                    break;
+                }
+                Err(PutError(rejected, _)) => value = Some(rejected),
+            }
+        }
+        leftover.extend(value);
+    }
+    leftover
+}
@@ -0,0 +1,235 @@
+use std::cmp::{Ord, Ordering};
+use std::collections::{BTreeMap, BinaryHeap};
+use std::sync::Arc;
+
+use crate::priority_queue::PrioritizedItem;
+use crate::queue::*;
+
+struct Entry<P> {
+    priority: P,
+    sequence: u64,
+}
+
+impl<P: Ord> Eq for Entry<P> {}
+
+impl<P: Ord> PartialEq<Self> for Entry<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<P: Ord> PartialOrd<Self> for Entry<P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P: Ord> Ord for Entry<P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// A heap which takes the oldest item instead of the highest priority one on every
+/// `every`th get.
+///
+/// Items are kept by insertion sequence, and the heap only holds their priorities. Taking
+/// the oldest item leaves its heap entry behind, which is skipped when it reaches the top.
+pub struct FairHeap<T, P> {
+    items: BTreeMap<u64, PrioritizedItem<T, P>>,
+    heap: BinaryHeap<Entry<P>>,
+    /// The sequence of the next item put, counting up from `FIRST_SEQUENCE`.
+    sequence: u64,
+    /// The sequence of the next item requeued as the oldest, counting down from below
+    /// `FIRST_SEQUENCE`, so it is older than any item held.
+    front: u64,
+    /// The sequence of the last item taken, which a requeue gives back to it.
+    taken: Option<u64>,
+    every: usize,
+    gets: usize,
+}
+
+/// Puts start halfway through the sequences, leaving the lower half for requeues.
+const FIRST_SEQUENCE: u64 = 1 << 63;
+
+impl<T, P> FairHeap<T, P> {
+    fn with_fairness(maxsize: Option<usize>, every: usize) -> Self {
+        Self {
+            items: BTreeMap::new(),
+            heap: match maxsize {
+                None => BinaryHeap::new(),
+                Some(s) => BinaryHeap::with_capacity(s),
+            },
+            sequence: FIRST_SEQUENCE,
+            front: FIRST_SEQUENCE - 1,
+            taken: None,
+            every,
+            gets: 0,
+        }
+    }
+}
+
+impl<T, P: Ord + Clone> FairHeap<T, P> {
+    fn get_highest(&mut self) -> Option<PrioritizedItem<T, P>> {
+        while let Some(entry) = self.heap.pop() {
+            if let Some(item) = self.items.remove(&entry.sequence) {
+                self.taken = Some(entry.sequence);
+                return Some(item);
+            }
+        }
+        None
+    }
+
+    fn get_oldest(&mut self) -> Option<PrioritizedItem<T, P>> {
+        let (sequence, item) = self.items.pop_first()?;
+        self.taken = Some(sequence);
+        if self.heap.len() > 2 * self.items.len() + 16 {
+            let items = &self.items;
+            self.heap
+                .retain(|entry| items.contains_key(&entry.sequence));
+        }
+        Some(item)
+    }
+
+    fn insert(&mut self, sequence: u64, value: PrioritizedItem<T, P>) {
+        self.heap.push(Entry {
+            priority: value.1.clone(),
+            sequence,
+        });
+        self.items.insert(sequence, value);
+    }
+}
+
+impl<T, P: Ord + Clone> BasicArray<PrioritizedItem<T, P>> for FairHeap<T, P> {
+    fn new(maxsize: Option<usize>) -> Self {
+        Self::with_fairness(maxsize, 0)
+    }
+
+    fn new_empty(&self, maxsize: Option<usize>) -> Self {
+        Self::with_fairness(maxsize, self.every)
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn get(&mut self) -> Option<PrioritizedItem<T, P>> {
+        if self.items.is_empty() {
+            return None;
+        }
+        self.gets += 1;
+        if self.every > 0 && self.gets >= self.every {
+            self.gets = 0;
+            self.get_oldest()
+        } else {
+            self.get_highest()
+        }
+    }
+
+    fn put(&mut self, value: PrioritizedItem<T, P>) {
+        self.insert(self.sequence, value);
+        self.sequence += 1;
+    }
+
+    /// The requeued item gets back the sequence of the last item taken, so it keeps its
+    /// age, assuming it is that item. Otherwise it is put as the oldest item.
+    fn requeue(&mut self, value: PrioritizedItem<T, P>) {
+        let sequence = match self.taken.take() {
+            Some(sequence) if !self.items.contains_key(&sequence) => sequence,
+            _ => {
+                let sequence = self.front;
+                self.front -= 1;
+                sequence
+            }
+        };
+        self.insert(sequence, value);
+    }
+
+    /// Runs in O(n) when the next get takes the highest priority item, since the top of
+    /// the heap may be an entry left behind by an item already taken.
+    fn peek(&self) -> Option<&PrioritizedItem<T, P>> {
+        if self.every > 0 && self.gets + 1 >= self.every {
+            return self.items.values().next();
+        }
+        self.heap
+            .iter()
+            .filter(|entry| self.items.contains_key(&entry.sequence))
+            .max()
+            .and_then(|entry| self.items.get(&entry.sequence))
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &PrioritizedItem<T, P>> + '_> {
+        Box::new(self.items.values())
+    }
+}
+
+impl<T, P: Ord + Clone> FairPriorityQueue<T, P> {
+    /// Creates a queue where every `every`th get takes the oldest item regardless of its
+    /// priority. An `every` of zero never does.
+    pub fn with_fairness(maxsize: Option<usize>, every: usize) -> Self {
+        Self {
+            inner: Arc::new(QueueInner::with_array(
+                FairHeap::with_fairness(maxsize, every),
+                maxsize,
+            )),
+        }
+    }
+}
+
+/// Queue with a priority, which periodically takes the oldest item so that no item waits
+/// behind higher priorities indefinitely.
+///
+/// # Example
+/// ```
+/// use rueue::{FairPriorityQueue, PrioritizedItem, Queue};
+///
+/// let queue = FairPriorityQueue::with_fairness(None, 3);
+///
+/// queue.put(PrioritizedItem("low", 0)).unwrap();
+/// let mut taken = Vec::new();
+/// for i in 0..10 {
+///     queue.put(PrioritizedItem("high", 10 + i)).unwrap();
+///     taken.push(queue.get().unwrap().0);
+/// }
+///
+/// let position = taken.iter().position(|item| *item == "low").unwrap();
+/// assert!(position < 3);
+/// ```
+///
+/// Only gets which take an item count towards `every`, and an item given back with
+/// `get_ack` keeps its age.
+/// ```
+/// use rueue::{FairPriorityQueue, PrioritizedItem, Queue, QueueError};
+///
+/// let queue = FairPriorityQueue::with_fairness(None, 2);
+/// assert_eq!(queue.get().unwrap_err(), QueueError::Empty);
+///
+/// queue.put(PrioritizedItem("old", 0)).unwrap();
+/// queue.put(PrioritizedItem("new", 1)).unwrap();
+/// drop(queue.get_ack(std::time::Duration::ZERO).unwrap());
+/// queue.put(PrioritizedItem("newer", 1)).unwrap();
+///
+/// assert_eq!(queue.get().unwrap().0, "old");
+/// assert_eq!(queue.get().unwrap().0, "new");
+/// assert_eq!(queue.get().unwrap().0, "newer");
+/// ```
+///
+/// An item given back after `take_all` emptied the queue is put as the oldest one.
+/// ```
+/// use std::time;
+/// use rueue::{FairPriorityQueue, PrioritizedItem, Queue};
+///
+/// let queue = FairPriorityQueue::with_fairness(None, 2);
+/// queue.put(PrioritizedItem("a", 1)).unwrap();
+/// let guard = queue.get_ack(time::Duration::ZERO).unwrap();
+/// assert!(queue.take_all().is_empty());
+/// drop(guard);
+/// queue.put(PrioritizedItem("b", 1)).unwrap();
+///
+/// assert_eq!(queue.len(), 2);
+/// assert_eq!(queue.get().unwrap().0, "a");
+/// assert_eq!(queue.get().unwrap().0, "b");
+/// ```
+pub type FairPriorityQueue<T, P> = BaseQueue<FairHeap<T, P>, PrioritizedItem<T, P>>;
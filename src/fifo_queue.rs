@@ -1,5 +1,8 @@
 use std::collections::VecDeque;
+use std::sync::Arc;
 
+#[cfg(feature = "audit")]
+use crate::audit::AuditOp;
 use crate::queue::*;
 
 impl<T> BasicArray<T> for VecDeque<T> {
@@ -21,6 +24,26 @@ impl<T> BasicArray<T> for VecDeque<T> {
     fn put(&mut self, value: T) {
         self.push_back(value)
     }
+
+    fn peek(&self) -> Option<&T> {
+        self.front()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(VecDeque::iter(self))
+    }
+
+    fn requeue(&mut self, value: T) {
+        self.push_front(value)
+    }
+
+    fn capacity(&self) -> usize {
+        VecDeque::capacity(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        VecDeque::shrink_to_fit(self)
+    }
 }
 
 /// Fifo (First in, First out) Queue.
@@ -29,7 +52,7 @@ impl<T> BasicArray<T> for VecDeque<T> {
 /// ```
 /// use rueue::{FifoQueue, Queue};
 ///
-/// let mut queue = FifoQueue::new(None);
+/// let queue = FifoQueue::new(None);
 ///
 /// queue.put(1).unwrap();
 /// queue.put(2).unwrap();
@@ -45,3 +68,376 @@ impl<T> BasicArray<T> for VecDeque<T> {
 /// assert_eq!(third_item, 3);
 /// ```
 pub type FifoQueue<T> = BaseQueue<VecDeque<T>, T>;
+
+/// Access to both ends of the queue. `PriorityQueue` has no meaningful ends, so it has no
+/// `first` or `last`.
+impl<T: Clone> FifoQueue<T> {
+    /// Returns a clone of the front item, the next one `get` would return.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// assert_eq!(queue.first(), None);
+    ///
+    /// queue.put(1).unwrap();
+    /// queue.put(2).unwrap();
+    /// queue.put(3).unwrap();
+    /// assert_eq!(queue.first(), Some(1));
+    /// assert_eq!(queue.len(), 3);
+    /// ```
+    pub fn first(&self) -> Option<T> {
+        self.inner.queue.lock().unwrap().front().cloned()
+    }
+
+    /// Returns a clone of the back item, the most recently put one.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// assert_eq!(queue.last(), None);
+    ///
+    /// queue.put(1).unwrap();
+    /// queue.put(2).unwrap();
+    /// queue.put(3).unwrap();
+    /// assert_eq!(queue.last(), Some(3));
+    /// assert_eq!(queue.len(), 3);
+    /// ```
+    pub fn last(&self) -> Option<T> {
+        self.inner.queue.lock().unwrap().back().cloned()
+    }
+
+    /// Moves the front item to the back in one locked step and returns a clone of it, for
+    /// round-robin scheduling over the queued items.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// assert_eq!(queue.rotate(), None);
+    ///
+    /// queue.put(1).unwrap();
+    /// queue.put(2).unwrap();
+    /// queue.put(3).unwrap();
+    ///
+    /// let visited: Vec<_> = (0..7).map(|_| queue.rotate().unwrap()).collect();
+    /// assert_eq!(visited, vec![1, 2, 3, 1, 2, 3, 1]);
+    /// assert_eq!(queue.len(), 3);
+    /// assert_eq!(queue.get().unwrap(), 2);
+    /// ```
+    pub fn rotate(&self) -> Option<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        if queue.is_empty() {
+            return None;
+        }
+        queue.rotate_left(1);
+        queue.back().cloned()
+    }
+}
+
+impl<T> FifoQueue<T> {
+    /// Keeps the `len` items at the front and removes the rest, returning them in the
+    /// order they would have been taken.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(5));
+    /// for i in 0..5 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// assert_eq!(queue.truncate(2), vec![2, 3, 4]);
+    /// assert!(queue.truncate(2).is_empty());
+    /// assert_eq!(queue.len(), 2);
+    /// assert_eq!(queue.get().unwrap(), 0);
+    /// assert_eq!(queue.get().unwrap(), 1);
+    /// ```
+    pub fn truncate(&self, len: usize) -> Vec<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        if queue.len() <= len {
+            return Vec::new();
+        }
+        let removed: Vec<T> = queue.split_off(len).into();
+        self.inner.record_len(queue.len());
+        drop(queue);
+        self.inner.notify_all_not_full();
+        removed
+    }
+
+    /// Lends up to `max` front items to `f` as one slice, without copying them, then
+    /// removes as many of them as `f` reports it consumed. The queue stays locked while
+    /// `f` runs.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(8));
+    /// for i in 0..8 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// let mut seen = Vec::new();
+    /// let consumed = queue.peek_slice(5, |items| {
+    ///     seen.extend_from_slice(items);
+    ///     3
+    /// });
+    /// assert_eq!(consumed, 3);
+    /// assert_eq!(seen, vec![0, 1, 2, 3, 4]);
+    /// assert_eq!(queue.len(), 5);
+    /// assert_eq!(queue.get().unwrap(), 3);
+    /// ```
+    pub fn peek_slice(&self, max: usize, f: impl FnOnce(&[T]) -> usize) -> usize {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let items = queue.make_contiguous();
+        let items = &items[..max.min(items.len())];
+        let consumed = f(items).min(items.len());
+        if consumed == 0 {
+            return 0;
+        }
+        queue.drain(..consumed);
+        self.inner.record_len(queue.len());
+        drop(queue);
+        self.inner.notify_all_not_full();
+        consumed
+    }
+
+    /// Puts all of `values` at the front of the queue, keeping their order, so that
+    /// `values[0]` is taken next. Fails without putting any of them if they don't all fit,
+    /// even once the `set_on_full` hook has had a chance to raise the bound, or if a rate
+    /// limit has fewer than `values.len()` puts left.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(Some(4));
+    /// queue.put(3).unwrap();
+    /// queue.extend_front(vec![1, 2]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     queue.extend_front(vec![-1, 0]),
+    ///     Err(PutError(vec![-1, 0], QueueError::Full))
+    /// );
+    /// queue.extend_front(vec![0]).unwrap();
+    /// assert!(queue.is_full());
+    ///
+    /// for i in 0..4 {
+    ///     assert_eq!(queue.get().unwrap(), i);
+    /// }
+    /// ```
+    ///
+    /// Each value counts against a rate limit like a separate put.
+    /// ```
+    /// use std::time;
+    /// use rueue::{FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::with_rate_limit(None, 3, time::Duration::from_secs(60));
+    /// queue.extend_front(vec![1, 2]).unwrap();
+    /// assert_eq!(
+    ///     queue.extend_front(vec![-1, 0]),
+    ///     Err(PutError(vec![-1, 0], QueueError::RateLimited))
+    /// );
+    /// queue.extend_front(vec![0]).unwrap();
+    /// assert_eq!(queue.put(3), Err(PutError(3, QueueError::RateLimited)));
+    /// assert_eq!(queue.len(), 3);
+    /// ```
+    pub fn extend_front(&self, values: Vec<T>) -> Result<(), PutError<Vec<T>>> {
+        if self.inner.is_closed() {
+            audit_event!(self.inner, AuditOp::Reject(QueueError::Closed), self.len());
+            return Err(PutError(values, QueueError::Closed));
+        }
+        if values.is_empty() {
+            return Ok(());
+        }
+        let mut queue = self.inner.queue.lock().unwrap();
+        // The last value fits if the queue isn't full once the others are in.
+        let last = queue.len() + values.len() - 1;
+        if self.inner.is_full_at(last) && !self.grow_on_full(last) {
+            trace_event!(len = queue.len(), "full");
+            audit_event!(self.inner, AuditOp::Reject(QueueError::Full), queue.len());
+            return Err(PutError(values, QueueError::Full));
+        }
+        if let Some(bucket) = &self.inner.rate_limit {
+            if !bucket.lock().unwrap().try_acquire_many(values.len()) {
+                audit_event!(
+                    self.inner,
+                    AuditOp::Reject(QueueError::RateLimited),
+                    queue.len()
+                );
+                return Err(PutError(values, QueueError::RateLimited));
+            }
+        }
+        let count = values.len();
+        for value in values.into_iter().rev() {
+            queue.push_front(value);
+            audit_event!(self.inner, AuditOp::Put, queue.len());
+        }
+        let len = queue.len();
+        self.inner.debug_assert_within_bound(len);
+        self.inner.record_len(len);
+        drop(queue);
+        trace_event!(len, count, "extend_front");
+        self.inner.notify_not_empty_count(count);
+        Ok(())
+    }
+
+    /// Takes every item matching `pred`, front to back, leaving the others queued in
+    /// their order.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for i in 0..8 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// assert_eq!(queue.get_matching(|i| i % 2 == 1), vec![1, 3, 5, 7]);
+    /// assert!(queue.get_matching(|i| i % 2 == 1).is_empty());
+    /// for i in [0, 2, 4, 6] {
+    ///     assert_eq!(queue.get().unwrap(), i);
+    /// }
+    /// ```
+    pub fn get_matching(&self, mut pred: impl FnMut(&T) -> bool) -> Vec<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let (matched, kept): (Vec<T>, Vec<T>) = queue.drain(..).partition(|item| pred(item));
+        *queue = kept.into();
+        if !matched.is_empty() {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        if !matched.is_empty() {
+            self.inner.notify_all_not_full();
+        }
+        matched
+    }
+
+    /// Applies `f` to every queued item in place, front to back, without taking any. The
+    /// queue stays locked while `f` runs.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for i in 0..3 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// queue.map_in_place(|item| *item += 10);
+    /// assert_eq!(queue.get().unwrap(), 10);
+    /// assert_eq!(queue.get().unwrap(), 11);
+    /// assert_eq!(queue.get().unwrap(), 12);
+    /// ```
+    pub fn map_in_place(&self, f: impl FnMut(&mut T)) {
+        self.inner.queue.lock().unwrap().iter_mut().for_each(f);
+    }
+
+    /// Sorts the queued items by `f`, so that they are taken in ascending order of key
+    /// from now on. This deliberately gives up first in, first out order; items with
+    /// equal keys keep their order. Items put later are still put at the back.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for (job, deadline) in [("c", 30), ("a", 10), ("b", 20), ("d", 10)] {
+    ///     queue.put((job, deadline)).unwrap();
+    /// }
+    ///
+    /// queue.sort_by_key(|(_, deadline)| *deadline);
+    /// assert_eq!(queue.get().unwrap().0, "a");
+    /// assert_eq!(queue.get().unwrap().0, "d");
+    /// assert_eq!(queue.get().unwrap().0, "b");
+    /// assert_eq!(queue.get().unwrap().0, "c");
+    /// ```
+    pub fn sort_by_key<K: Ord>(&self, f: impl FnMut(&T) -> K) {
+        self.inner
+            .queue
+            .lock()
+            .unwrap()
+            .make_contiguous()
+            .sort_by_key(f);
+    }
+}
+
+impl<T: PartialEq> FifoQueue<T> {
+    /// Removes and returns the first item equal to `target`, leaving the other items in
+    /// order.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(4));
+    /// for i in [1, 2, 3, 2] {
+    ///     queue.put(i).unwrap();
+    /// }
+    /// assert!(queue.is_full());
+    ///
+    /// assert_eq!(queue.take(&2), Some(2));
+    /// assert_eq!(queue.take(&5), None);
+    /// assert!(!queue.is_full());
+    ///
+    /// assert_eq!(queue.get().unwrap(), 1);
+    /// assert_eq!(queue.get().unwrap(), 3);
+    /// assert_eq!(queue.get().unwrap(), 2);
+    /// ```
+    pub fn take(&self, target: &T) -> Option<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let index = queue.iter().position(|value| value == target)?;
+        let value = queue.remove(index);
+        self.inner.record_len(queue.len());
+        drop(queue);
+        self.inner.notify_not_full();
+        value
+    }
+}
+
+/// Creates an unbounded queue holding the items of `values`, the first of which is taken first.
+///
+/// # Example
+/// ```
+/// use std::collections::VecDeque;
+/// use rueue::{FifoQueue, Queue};
+///
+/// let queue = FifoQueue::from(VecDeque::from(vec![1, 2, 3]));
+///
+/// assert_eq!(queue.get().unwrap(), 1);
+/// assert_eq!(queue.get().unwrap(), 2);
+/// assert_eq!(queue.get().unwrap(), 3);
+/// ```
+impl<T> From<VecDeque<T>> for FifoQueue<T> {
+    fn from(values: VecDeque<T>) -> Self {
+        Self {
+            inner: Arc::new(QueueInner::with_array(values, None)),
+        }
+    }
+}
+
+/// Creates an unbounded queue holding the items of `values`, the first of which is taken first.
+///
+/// # Example
+/// ```
+/// use rueue::{FifoQueue, Queue};
+///
+/// let queue = FifoQueue::from(vec![1, 2, 3]);
+///
+/// assert_eq!(queue.get().unwrap(), 1);
+/// assert_eq!(queue.get().unwrap(), 2);
+/// assert_eq!(queue.get().unwrap(), 3);
+/// ```
+impl<T> From<Vec<T>> for FifoQueue<T> {
+    fn from(values: Vec<T>) -> Self {
+        VecDeque::from(values).into()
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::queue::*;
+
+/// How many buckets a length histogram has. Bucket 0 counts an empty queue, bucket `i`
+/// lengths from `2^(i-1)` up to `2^i - 1`, and the last bucket every longer length too.
+pub const LENGTH_BUCKETS: usize = 16;
+
+/// Counts of the lengths a queue was left at, in power-of-two buckets.
+pub(crate) struct LengthHistogram {
+    buckets: [AtomicU64; LENGTH_BUCKETS],
+}
+
+impl LengthHistogram {
+    pub(crate) fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+
+    pub(crate) fn record(&self, len: usize) {
+        let bucket = (usize::BITS - len.leading_zeros()) as usize;
+        self.buckets[bucket.min(LENGTH_BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Returns how often each range of lengths was seen, sampled after every change to the
+    /// queue, in the buckets described at `LENGTH_BUCKETS`. Counts in the bucket of the
+    /// maxsize show how often the bound was reached. Only available with the `histogram`
+    /// feature.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for i in 0..5 {
+    ///     queue.put(i).unwrap();
+    /// }
+    /// for _ in 0..5 {
+    ///     queue.get().unwrap();
+    /// }
+    ///
+    /// // The puts leave lengths 1 to 5, and the gets 4 down to 0.
+    /// let histogram = queue.length_histogram();
+    /// assert_eq!(histogram[..5], [1, 2, 4, 3, 0]);
+    /// assert_eq!(histogram.iter().sum::<u64>(), 10);
+    ///
+    /// // Failed takes and changes which leave the length alone are not sampled.
+    /// assert!(queue.get().is_err());
+    /// queue.set_reserved_slots(1);
+    /// assert_eq!(queue.length_histogram(), histogram);
+    /// ```
+    pub fn length_histogram(&self) -> [u64; LENGTH_BUCKETS] {
+        std::array::from_fn(|i| self.inner.histogram.buckets[i].load(Ordering::Relaxed))
+    }
+}
@@ -7,9 +7,9 @@
 //!
 //! use rueue::{FifoQueue, Queue};
 //!
-//! let mut queue = FifoQueue::new(None);
+//! let queue = FifoQueue::new(None);
 //!
-//! let mut q1 = queue.clone();
+//! let q1 = queue.clone();
 //! let th1 = thread::spawn(move || {
 //!     for i in 0..3 {
 //!         q1.put_wait(i, time::Duration::from_millis(100)).unwrap();
@@ -17,7 +17,7 @@
 //!     }
 //! });
 //!
-//! let mut q2 = queue.clone();
+//! let q2 = queue.clone();
 //! let th2 = thread::spawn(move || {
 //!     if let Ok(item) = q2.get_wait(time::Duration::from_millis(0)) {
 //!         assert_eq!(item, 0);
@@ -33,15 +33,175 @@
 //! th1.join().unwrap();
 //! th2.join().unwrap();
 //! ```
+//!
+//! # Features
+//!
+//! - `tracing`: emits `trace!` events through the `tracing` crate when items are put or
+//!   taken, or a put or get fails because the queue is full or empty. Each event records
+//!   the queue length. Without the feature no events are compiled in.
+//!
+//! - `audit`: keeps a log of the most recent puts, gets and rejected puts of each queue,
+//!   read with `audit_log`.
+//!
+//! - `histogram`: counts the lengths each queue is left at by its puts and takes in
+//!   power-of-two buckets, read with `length_histogram`.
+//!
+//! ```
+//! # #[cfg(feature = "tracing")]
+//! # {
+//! use std::fmt;
+//! use std::sync::{Arc, Mutex};
+//! use tracing::field::{Field, Visit};
+//! use tracing::span::{Attributes, Id, Record};
+//! use tracing::{Event, Metadata, Subscriber};
+//!
+//! use rueue::{FifoQueue, Queue};
+//!
+//! #[derive(Clone, Default)]
+//! struct Recorder(Arc<Mutex<Vec<String>>>);
+//!
+//! impl Visit for Recorder {
+//!     fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+//!         self.0.lock().unwrap().push(format!("{}={:?}", field.name(), value));
+//!     }
+//! }
+//!
+//! impl Subscriber for Recorder {
+//!     fn enabled(&self, _: &Metadata<'_>) -> bool {
+//!         true
+//!     }
+//!     fn new_span(&self, _: &Attributes<'_>) -> Id {
+//!         Id::from_u64(1)
+//!     }
+//!     fn record(&self, _: &Id, _: &Record<'_>) {}
+//!     fn record_follows_from(&self, _: &Id, _: &Id) {}
+//!     fn event(&self, event: &Event<'_>) {
+//!         event.record(&mut self.clone());
+//!     }
+//!     fn enter(&self, _: &Id) {}
+//!     fn exit(&self, _: &Id) {}
+//! }
+//!
+//! let recorder = Recorder::default();
+//! let events = Arc::clone(&recorder.0);
+//! tracing::subscriber::with_default(recorder, || {
+//!     let queue = FifoQueue::new(Some(1));
+//!     queue.put(1).unwrap();
+//!     assert!(queue.put(2).is_err());
+//!     queue.get().unwrap();
+//!     assert!(queue.get().is_err());
+//! });
+//!
+//! assert_eq!(
+//!     *events.lock().unwrap(),
+//!     vec![
+//!         "message=put", "len=1",
+//!         "message=full", "len=1",
+//!         "message=get", "len=0",
+//!         "message=empty", "len=0",
+//!     ]
+//! );
+//! # }
+//! ```
 
+#[macro_use]
 mod queue;
-pub use queue::{PutError, Queue, QueueError};
+pub use queue::{BoxedQueue, PutError, PutOutcome, Queue, QueueError, TryPutError, WaitState};
 
 mod fifo_queue;
 pub use fifo_queue::FifoQueue;
 
 mod lifo_queue;
-pub use lifo_queue::LifoQueue;
+pub use lifo_queue::{DequeLifoQueue, LifoQueue};
 
 mod priority_queue;
-pub use priority_queue::{PrioritizedItem, PriorityQueue};
+pub use priority_queue::{PrioritizedItem, Priority, PriorityQueue};
+
+mod aging_priority_queue;
+pub use aging_priority_queue::AgingPriorityQueue;
+
+mod fair_priority_queue;
+pub use fair_priority_queue::FairPriorityQueue;
+
+mod value_ordered_priority_queue;
+pub use value_ordered_priority_queue::{TotalPrioritizedItem, ValueOrderedPriorityQueue};
+
+mod capped_priority_queue;
+pub use capped_priority_queue::CappedPriorityQueue;
+
+mod sequenced_priority_queue;
+pub use sequenced_priority_queue::{SequencedPriorityQueue, TieBreak};
+
+mod timed_priority_queue;
+pub use timed_priority_queue::TimedPriorityQueue;
+
+mod rate_limited_queue;
+pub use rate_limited_queue::RateLimitedQueue;
+
+mod queue_group;
+pub use queue_group::QueueGroup;
+
+mod producer;
+pub use producer::Producer;
+
+mod ack_guard;
+pub use ack_guard::AckGuard;
+
+mod permit;
+pub use permit::Permit;
+
+mod persist;
+
+mod option_queue;
+pub use option_queue::OptionQueueExt;
+
+mod park_queue;
+pub use park_queue::ParkQueue;
+
+mod close_guard;
+pub use close_guard::CloseGuard;
+
+mod map_err;
+pub use map_err::MapErrExt;
+
+mod sharded_queue;
+pub use sharded_queue::ShardedQueue;
+
+mod queue_guard;
+pub use queue_guard::QueueGuard;
+
+mod dedup_queue;
+pub use dedup_queue::{DedupQueue, Keyed};
+
+mod cancellation;
+pub use cancellation::CancellationToken;
+
+mod lossy_queue;
+pub use lossy_queue::LossyQueue;
+
+mod timed_queue;
+pub use timed_queue::TimedQueue;
+
+#[cfg(feature = "audit")]
+mod audit;
+#[cfg(feature = "audit")]
+pub use audit::{AuditEntry, AuditOp};
+
+mod rw_queue;
+pub use rw_queue::RwQueue;
+
+mod distribute;
+pub use distribute::distribute;
+
+mod watch;
+pub use watch::LengthWatcher;
+
+mod shutdown;
+
+#[cfg(feature = "histogram")]
+mod histogram;
+#[cfg(feature = "histogram")]
+pub use histogram::LENGTH_BUCKETS;
+
+mod worker_pool;
+pub use worker_pool::WorkerHandle;
@@ -1,3 +1,7 @@
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
 use crate::queue::*;
 
 impl<T> BasicArray<T> for Vec<T> {
@@ -19,6 +23,22 @@ impl<T> BasicArray<T> for Vec<T> {
     fn put(&mut self, value: T) {
         self.push(value)
     }
+
+    fn peek(&self) -> Option<&T> {
+        self.last()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(<[T]>::iter(self))
+    }
+
+    fn capacity(&self) -> usize {
+        Vec::capacity(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        Vec::shrink_to_fit(self)
+    }
 }
 
 /// Lifo (Last in, First out) Queue.
@@ -27,7 +47,7 @@ impl<T> BasicArray<T> for Vec<T> {
 /// ```
 /// use rueue::{LifoQueue, Queue};
 ///
-/// let mut queue = LifoQueue::new(None);
+/// let queue = LifoQueue::new(None);
 ///
 /// queue.put(1).unwrap();
 /// queue.put(2).unwrap();
@@ -42,4 +62,330 @@ impl<T> BasicArray<T> for Vec<T> {
 /// let third_item = queue.get().unwrap();
 /// assert_eq!(third_item, 1);
 /// ```
+///
+/// The top of the stack is the back of the backing `Vec`, for every operation which
+/// takes, requeues or evicts items.
+/// ```
+/// use std::time;
+/// use rueue::{LifoQueue, Queue};
+///
+/// let queue = LifoQueue::new(Some(16));
+/// let mut model = Vec::new();
+/// let mut seed: u32 = 0x2545_f491;
+/// for i in 0..10_000 {
+///     seed ^= seed << 13;
+///     seed ^= seed >> 17;
+///     seed ^= seed << 5;
+///     match seed % 4 {
+///         0 | 1 => {
+///             if queue.put(i).is_ok() {
+///                 model.push(i);
+///             } else {
+///                 assert_eq!(model.len(), 16);
+///             }
+///         }
+///         2 => assert_eq!(queue.get().ok(), model.pop()),
+///         _ => {
+///             if let Ok(item) = queue.get_ack(time::Duration::from_micros(1)) {
+///                 assert_eq!(Some(*item), model.last().copied());
+///             }
+///         }
+///     }
+///     assert_eq!(queue.last(), model.last().copied());
+///     assert_eq!(queue.first(), model.first().copied());
+/// }
+/// assert_eq!(queue.set_maxsize_evict(0), model.into_iter().rev().collect::<Vec<_>>());
+/// ```
 pub type LifoQueue<T> = BaseQueue<Vec<T>, T>;
+
+/// Access to both ends of the stack, like `FifoQueue::first` and `FifoQueue::last`.
+impl<T: Clone> LifoQueue<T> {
+    /// Returns a clone of the bottom item, the least recently put one.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{LifoQueue, Queue};
+    ///
+    /// let queue = LifoQueue::new(None);
+    /// assert_eq!(queue.first(), None);
+    ///
+    /// queue.put(1).unwrap();
+    /// queue.put(2).unwrap();
+    /// queue.put(3).unwrap();
+    /// assert_eq!(queue.first(), Some(1));
+    /// assert_eq!(queue.len(), 3);
+    /// ```
+    pub fn first(&self) -> Option<T> {
+        self.inner.queue.lock().unwrap().first().cloned()
+    }
+
+    /// Returns a clone of the top item, the next one `get` would return.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{LifoQueue, Queue};
+    ///
+    /// let queue = LifoQueue::new(None);
+    /// assert_eq!(queue.last(), None);
+    ///
+    /// queue.put(1).unwrap();
+    /// queue.put(2).unwrap();
+    /// queue.put(3).unwrap();
+    /// assert_eq!(queue.last(), Some(3));
+    /// assert_eq!(queue.len(), 3);
+    /// ```
+    pub fn last(&self) -> Option<T> {
+        self.inner.queue.lock().unwrap().last().cloned()
+    }
+
+    /// Returns a clone of the item `k` below the top, where the top item is at depth 0.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{LifoQueue, Queue};
+    ///
+    /// let queue = LifoQueue::new(None);
+    ///
+    /// queue.put(1).unwrap();
+    /// queue.put(2).unwrap();
+    /// queue.put(3).unwrap();
+    /// assert_eq!(queue.peek_depth(0), Some(3));
+    /// assert_eq!(queue.peek_depth(1), Some(2));
+    /// assert_eq!(queue.peek_depth(2), Some(1));
+    /// assert_eq!(queue.peek_depth(3), None);
+    /// assert_eq!(queue.len(), 3);
+    /// ```
+    pub fn peek_depth(&self, k: usize) -> Option<T> {
+        let queue = self.inner.queue.lock().unwrap();
+        let index = queue.len().checked_sub(k + 1)?;
+        queue[..].get(index).cloned()
+    }
+}
+
+impl<T> LifoQueue<T> {
+    /// Keeps the `len` most recently put items and removes the rest, returning them in
+    /// the order they would have been taken.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{LifoQueue, Queue};
+    ///
+    /// let queue = LifoQueue::new(Some(5));
+    /// for i in 0..5 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// assert_eq!(queue.truncate(2), vec![2, 1, 0]);
+    /// assert!(queue.truncate(2).is_empty());
+    /// assert_eq!(queue.len(), 2);
+    /// assert_eq!(queue.get().unwrap(), 4);
+    /// assert_eq!(queue.get().unwrap(), 3);
+    /// ```
+    pub fn truncate(&self, len: usize) -> Vec<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let Some(excess) = queue.len().checked_sub(len).filter(|excess| *excess > 0) else {
+            return Vec::new();
+        };
+        let removed: Vec<T> = queue.drain(..excess).rev().collect();
+        self.inner.record_len(queue.len());
+        drop(queue);
+        self.inner.notify_all_not_full();
+        removed
+    }
+
+    /// Takes every item matching `pred`, returning them in the order they would have been
+    /// taken, and leaves the others queued in their order. `pred` sees the items oldest
+    /// first.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{LifoQueue, Queue};
+    ///
+    /// let queue = LifoQueue::new(None);
+    /// for i in 0..8 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// assert_eq!(queue.get_matching(|i| i % 2 == 1), vec![7, 5, 3, 1]);
+    /// for i in [6, 4, 2, 0] {
+    ///     assert_eq!(queue.get().unwrap(), i);
+    /// }
+    /// ```
+    pub fn get_matching(&self, mut pred: impl FnMut(&T) -> bool) -> Vec<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let (mut matched, kept): (Vec<T>, Vec<T>) = queue.drain(..).partition(|item| pred(item));
+        *queue = kept;
+        if !matched.is_empty() {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        if !matched.is_empty() {
+            self.inner.notify_all_not_full();
+        }
+        matched.reverse();
+        matched
+    }
+
+    /// Applies `f` to every queued item in place, without taking any. The queue stays
+    /// locked while `f` runs.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{LifoQueue, Queue};
+    ///
+    /// let queue = LifoQueue::new(None);
+    /// queue.put("a".to_string()).unwrap();
+    /// queue.put("b".to_string()).unwrap();
+    ///
+    /// queue.map_in_place(|item| item.make_ascii_uppercase());
+    /// assert_eq!(queue.get().unwrap(), "B");
+    /// assert_eq!(queue.get().unwrap(), "A");
+    /// ```
+    pub fn map_in_place(&self, f: impl FnMut(&mut T)) {
+        self.inner.queue.lock().unwrap().iter_mut().for_each(f);
+    }
+
+    /// Sorts the queued items by `f`, so that they are taken in ascending order of key
+    /// from now on. This deliberately gives up last in, first out order; items with
+    /// equal keys keep their order. Items put later are still taken first.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{LifoQueue, Queue};
+    ///
+    /// let queue = LifoQueue::new(None);
+    /// for (job, deadline) in [("c", 30), ("a", 10), ("b", 20), ("d", 10)] {
+    ///     queue.put((job, deadline)).unwrap();
+    /// }
+    ///
+    /// queue.sort_by_key(|(_, deadline)| *deadline);
+    /// assert_eq!(queue.get().unwrap().0, "d");
+    /// assert_eq!(queue.get().unwrap().0, "a");
+    /// assert_eq!(queue.get().unwrap().0, "b");
+    /// assert_eq!(queue.get().unwrap().0, "c");
+    /// ```
+    pub fn sort_by_key<K: Ord>(&self, mut f: impl FnMut(&T) -> K) {
+        // Items are taken from the back, so sort descending to take them ascending.
+        self.inner
+            .queue
+            .lock()
+            .unwrap()
+            .sort_by_key(|item| Reverse(f(item)));
+    }
+}
+
+impl<T: PartialEq> LifoQueue<T> {
+    /// Removes and returns the item equal to `target` nearest the top, leaving the other
+    /// items in order.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{LifoQueue, Queue};
+    ///
+    /// let queue = LifoQueue::new(None);
+    /// for i in [1, 2, 3, 4] {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// assert_eq!(queue.take(&2), Some(2));
+    /// assert_eq!(queue.take(&5), None);
+    ///
+    /// assert_eq!(queue.get().unwrap(), 4);
+    /// assert_eq!(queue.get().unwrap(), 3);
+    /// assert_eq!(queue.get().unwrap(), 1);
+    /// ```
+    pub fn take(&self, target: &T) -> Option<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let index = queue[..].iter().rposition(|value| value == target)?;
+        let value = queue.remove(index);
+        self.inner.record_len(queue.len());
+        drop(queue);
+        self.inner.notify_not_full();
+        Some(value)
+    }
+}
+
+/// Creates an unbounded queue holding the items of `values` as if they had been put in
+/// order, so the last one is taken first.
+///
+/// # Example
+/// ```
+/// use rueue::{LifoQueue, Queue};
+///
+/// let queue = LifoQueue::from(vec![1, 2, 3]);
+///
+/// assert_eq!(queue.get().unwrap(), 3);
+/// assert_eq!(queue.get().unwrap(), 2);
+/// assert_eq!(queue.get().unwrap(), 1);
+/// ```
+impl<T> From<Vec<T>> for LifoQueue<T> {
+    fn from(values: Vec<T>) -> Self {
+        Self {
+            inner: Arc::new(QueueInner::with_array(values, None)),
+        }
+    }
+}
+
+/// A stack over a `VecDeque`, pushing and popping at its back.
+pub struct DequeStack<T>(VecDeque<T>);
+
+impl<T> BasicArray<T> for DequeStack<T> {
+    fn new(maxsize: Option<usize>) -> Self {
+        match maxsize {
+            None => Self(VecDeque::new()),
+            Some(s) => Self(VecDeque::with_capacity(s)),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+
+    fn put(&mut self, value: T) {
+        self.0.push_back(value)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.0.back()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(self.0.iter())
+    }
+
+    fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.0.shrink_to_fit()
+    }
+}
+
+/// Lifo Queue backed by a `VecDeque`, taking the same items in the same order as
+/// `LifoQueue` while allowing access to both ends of the backing store.
+///
+/// # Example
+/// ```
+/// use rueue::{DequeLifoQueue, LifoQueue, Queue};
+///
+/// let deque = DequeLifoQueue::new(None);
+/// let vec = LifoQueue::new(None);
+///
+/// for i in 0..10 {
+///     deque.put(i).unwrap();
+///     vec.put(i).unwrap();
+///     if i % 3 == 0 {
+///         assert_eq!(deque.get().unwrap(), vec.get().unwrap());
+///     }
+/// }
+/// while let Ok(item) = vec.get() {
+///     assert_eq!(deque.get().unwrap(), item);
+/// }
+/// assert!(deque.is_empty());
+/// ```
+pub type DequeLifoQueue<T> = BaseQueue<DequeStack<T>, T>;
@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time;
+
+use crate::queue::*;
+
+/// A ring holding at most `capacity` items, which drops the oldest one to make room.
+struct LossyRing<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> BasicArray<T> for LossyRing<T> {
+    fn new(maxsize: Option<usize>) -> Self {
+        let capacity = maxsize.unwrap_or(usize::MAX);
+        Self {
+            items: match maxsize {
+                None => VecDeque::new(),
+                Some(s) => VecDeque::with_capacity(s),
+            },
+            capacity,
+        }
+    }
+
+    fn new_empty(&self, _maxsize: Option<usize>) -> Self {
+        Self::new(Some(self.capacity))
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn get(&mut self) -> Option<T> {
+        self.items.pop_front()
+    }
+
+    fn put(&mut self, value: T) {
+        if self.items.len() >= self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(value)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.items.front()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(self.items.iter())
+    }
+
+    fn requeue(&mut self, value: T) {
+        self.items.push_front(value)
+    }
+
+    fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.items.shrink_to_fit()
+    }
+}
+
+/// Fifo Queue keeping only the `capacity` most recently put items. A put into a full
+/// queue never fails or blocks; it drops the oldest item instead.
+///
+/// # Example
+/// ```
+/// use rueue::{LossyQueue, Queue};
+///
+/// let queue = LossyQueue::new(3);
+/// for i in 0..6 {
+///     queue.put(i);
+/// }
+///
+/// assert_eq!(queue.len(), 3);
+/// assert_eq!(queue.get().unwrap(), 3);
+/// assert_eq!(queue.get().unwrap(), 4);
+/// assert_eq!(queue.get().unwrap(), 5);
+/// assert!(queue.is_empty());
+/// ```
+pub struct LossyQueue<T> {
+    queue: BaseQueue<LossyRing<T>, T>,
+    capacity: usize,
+}
+
+impl<T> LossyQueue<T> {
+    /// Creates a queue keeping at most `capacity` items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "a lossy queue must keep at least one item");
+        // The ring enforces the capacity itself, so the queue is unbounded and never
+        // rejects a put as full.
+        let ring = LossyRing::new(Some(capacity));
+        Self {
+            queue: BaseQueue {
+                inner: Arc::new(QueueInner::with_array(ring, None)),
+            },
+            capacity,
+        }
+    }
+
+    /// Puts `value`, dropping the oldest item if the queue holds `capacity` items.
+    pub fn put(&self, value: T) {
+        if let Err(PutError(_, err)) = self.queue.put(value) {
+            unreachable!("put into a lossy queue failed with {:?}", err);
+        }
+    }
+}
+
+impl<T> Queue<T> for LossyQueue<T> {
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Whether the next put will drop the oldest item.
+    fn is_full(&self) -> bool {
+        self.len() >= self.capacity
+    }
+
+    fn is_bounded(&self) -> bool {
+        true
+    }
+
+    fn maxsize(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
+
+    fn get(&self) -> Result<T, QueueError> {
+        self.queue.get()
+    }
+
+    fn get_wait(&self, timeout: time::Duration) -> Result<T, QueueError> {
+        self.queue.get_wait(timeout)
+    }
+
+    fn put(&self, value: T) -> Result<(), PutError<T>> {
+        LossyQueue::put(self, value);
+        Ok(())
+    }
+
+    fn put_wait(&self, value: T, _timeout: time::Duration) -> Result<(), PutError<T>> {
+        LossyQueue::put(self, value);
+        Ok(())
+    }
+}
+
+impl<T> Clone for LossyQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: self.queue.clone(),
+            capacity: self.capacity,
+        }
+    }
+}
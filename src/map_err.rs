@@ -0,0 +1,85 @@
+use std::time;
+
+use crate::queue::{PutError, Queue, QueueError};
+
+/// Helpers mapping queue errors into the caller's own error type inline, so they can be
+/// propagated with `?`.
+///
+/// # Example
+/// ```
+/// use std::time;
+/// use rueue::{FifoQueue, MapErrExt, PutError, Queue, QueueError};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum AppError {
+///     NoWork,
+///     Backlogged(u32),
+/// }
+///
+/// fn relay(src: &FifoQueue<u32>, dst: &FifoQueue<u32>) -> Result<u32, AppError> {
+///     let value = src.get_or(|_| AppError::NoWork)?;
+///     dst.put_or(value, |PutError(value, _)| AppError::Backlogged(value))?;
+///     Ok(value)
+/// }
+///
+/// let src = FifoQueue::new(None);
+/// let dst = FifoQueue::new(Some(1));
+/// assert_eq!(relay(&src, &dst), Err(AppError::NoWork));
+///
+/// src.put(1).unwrap();
+/// src.put(2).unwrap();
+/// assert_eq!(relay(&src, &dst), Ok(1));
+/// assert_eq!(relay(&src, &dst), Err(AppError::Backlogged(2)));
+///
+/// let err = src.get_wait_or(time::Duration::from_millis(10), |err| err);
+/// assert_eq!(err, Err(QueueError::Empty));
+/// ```
+pub trait MapErrExt<T> {
+    /// Like `get`, mapping the error with `f`.
+    fn get_or<E>(&self, f: impl FnOnce(QueueError) -> E) -> Result<T, E>;
+
+    /// Like `get_wait`, mapping the error with `f`.
+    fn get_wait_or<E>(
+        &self,
+        timeout: time::Duration,
+        f: impl FnOnce(QueueError) -> E,
+    ) -> Result<T, E>;
+
+    /// Like `put`, mapping the error, which still holds the rejected value, with `f`.
+    fn put_or<E>(&self, value: T, f: impl FnOnce(PutError<T>) -> E) -> Result<(), E>;
+
+    /// Like `put_wait`, mapping the error, which still holds the rejected value, with `f`.
+    fn put_wait_or<E>(
+        &self,
+        value: T,
+        timeout: time::Duration,
+        f: impl FnOnce(PutError<T>) -> E,
+    ) -> Result<(), E>;
+}
+
+impl<T, Q: Queue<T> + ?Sized> MapErrExt<T> for Q {
+    fn get_or<E>(&self, f: impl FnOnce(QueueError) -> E) -> Result<T, E> {
+        self.get().map_err(f)
+    }
+
+    fn get_wait_or<E>(
+        &self,
+        timeout: time::Duration,
+        f: impl FnOnce(QueueError) -> E,
+    ) -> Result<T, E> {
+        self.get_wait(timeout).map_err(f)
+    }
+
+    fn put_or<E>(&self, value: T, f: impl FnOnce(PutError<T>) -> E) -> Result<(), E> {
+        self.put(value).map_err(f)
+    }
+
+    fn put_wait_or<E>(
+        &self,
+        value: T,
+        timeout: time::Duration,
+        f: impl FnOnce(PutError<T>) -> E,
+    ) -> Result<(), E> {
+        self.put_wait(value, timeout).map_err(f)
+    }
+}
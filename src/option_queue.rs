@@ -0,0 +1,36 @@
+use crate::queue::{Queue, QueueError};
+
+/// Helpers for queues carrying `Option<T>`, where `None` items are sentinels to skip.
+pub trait OptionQueueExt<T> {
+    /// Takes items until one is `Some`, discarding the `None`s before it. Returns
+    /// `QueueError::Empty` if the queue runs out first.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, OptionQueueExt, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// queue.put(None).unwrap();
+    /// queue.put(Some(1)).unwrap();
+    /// queue.put(None).unwrap();
+    /// queue.put(None).unwrap();
+    /// queue.put(Some(2)).unwrap();
+    /// queue.put(None).unwrap();
+    ///
+    /// assert_eq!(queue.get_skip_none().unwrap(), 1);
+    /// assert_eq!(queue.get_skip_none().unwrap(), 2);
+    /// assert!(queue.get_skip_none().is_err());
+    /// assert!(queue.is_empty());
+    /// ```
+    fn get_skip_none(&self) -> Result<T, QueueError>;
+}
+
+impl<T, Q: Queue<Option<T>> + ?Sized> OptionQueueExt<T> for Q {
+    fn get_skip_none(&self) -> Result<T, QueueError> {
+        loop {
+            if let Some(value) = self.get()? {
+                return Ok(value);
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, Thread};
+use std::time;
+
+use crate::queue::*;
+
+struct ParkState<T> {
+    items: VecDeque<T>,
+    consumers: VecDeque<Thread>,
+    producers: VecDeque<Thread>,
+}
+
+struct ParkInner<T> {
+    state: Mutex<ParkState<T>>,
+    maxsize: Option<usize>,
+}
+
+/// Removes the current thread from `waiters`, where it may be left after a timeout or a
+/// spurious wakeup.
+fn deregister(waiters: &mut VecDeque<Thread>) {
+    let id = thread::current().id();
+    waiters.retain(|waiter| waiter.id() != id);
+}
+
+fn park(deadline: Option<time::Instant>) {
+    match deadline {
+        None => thread::park(),
+        Some(deadline) => {
+            thread::park_timeout(deadline.saturating_duration_since(time::Instant::now()))
+        }
+    }
+}
+
+/// Fifo Queue which blocks with `thread::park` instead of a `Condvar`.
+///
+/// A waiting thread registers its handle under the queue lock before parking, and is
+/// unparked by the thread which makes progress possible for it. An unpark which arrives
+/// before the park leaves the thread's token set, so the park returns at once and no
+/// wakeup is lost.
+///
+/// # Example
+/// ```
+/// use std::thread;
+/// use std::time;
+/// use rueue::{ParkQueue, Queue};
+///
+/// let queue = ParkQueue::new(Some(2));
+///
+/// let producer = queue.clone();
+/// let th = thread::spawn(move || {
+///     for i in 0..10_000 {
+///         producer.put_wait(i, time::Duration::ZERO).unwrap();
+///     }
+/// });
+///
+/// for i in 0..10_000 {
+///     assert_eq!(queue.get_wait(time::Duration::ZERO).unwrap(), i);
+/// }
+/// th.join().unwrap();
+/// assert!(queue.is_empty());
+/// ```
+pub struct ParkQueue<T> {
+    inner: Arc<ParkInner<T>>,
+}
+
+impl<T> ParkQueue<T> {
+    pub fn new(maxsize: Option<usize>) -> Self {
+        Self {
+            inner: Arc::new(ParkInner {
+                state: Mutex::new(ParkState {
+                    items: match maxsize {
+                        None => VecDeque::new(),
+                        Some(s) => VecDeque::with_capacity(s),
+                    },
+                    consumers: VecDeque::new(),
+                    producers: VecDeque::new(),
+                }),
+                maxsize,
+            }),
+        }
+    }
+
+    fn is_full_at(&self, len: usize) -> bool {
+        matches!(self.inner.maxsize, Some(maxsize) if len >= maxsize)
+    }
+
+    fn take(&self, mut state: MutexGuard<ParkState<T>>) -> Option<T> {
+        let value = state.items.pop_front()?;
+        let producer = state.producers.pop_front();
+        drop(state);
+        if let Some(producer) = producer {
+            producer.unpark();
+        }
+        Some(value)
+    }
+
+    fn push(&self, mut state: MutexGuard<ParkState<T>>, value: T) {
+        state.items.push_back(value);
+        let consumer = state.consumers.pop_front();
+        drop(state);
+        if let Some(consumer) = consumer {
+            consumer.unpark();
+        }
+    }
+}
+
+impl<T> Queue<T> for ParkQueue<T> {
+    fn len(&self) -> usize {
+        self.inner.state.lock().unwrap().items.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn is_full(&self) -> bool {
+        self.is_full_at(self.len())
+    }
+
+    fn is_bounded(&self) -> bool {
+        self.inner.maxsize.is_some()
+    }
+
+    fn maxsize(&self) -> Option<usize> {
+        self.inner.maxsize
+    }
+
+    fn get(&self) -> Result<T, QueueError> {
+        self.take(self.inner.state.lock().unwrap())
+            .ok_or(QueueError::Empty)
+    }
+
+    fn get_wait(&self, timeout: time::Duration) -> Result<T, QueueError> {
+        let deadline = (!timeout.is_zero()).then(|| time::Instant::now() + timeout);
+        loop {
+            let mut state = self.inner.state.lock().unwrap();
+            deregister(&mut state.consumers);
+            if !state.items.is_empty() {
+                return Ok(self.take(state).unwrap());
+            }
+            if matches!(deadline, Some(deadline) if time::Instant::now() >= deadline) {
+                return Err(QueueError::Empty);
+            }
+            state.consumers.push_back(thread::current());
+            drop(state);
+            park(deadline);
+        }
+    }
+
+    fn put(&self, value: T) -> Result<(), PutError<T>> {
+        let state = self.inner.state.lock().unwrap();
+        if self.is_full_at(state.items.len()) {
+            return Err(PutError(value, QueueError::Full));
+        }
+        self.push(state, value);
+        Ok(())
+    }
+
+    fn put_wait(&self, value: T, timeout: time::Duration) -> Result<(), PutError<T>> {
+        let deadline = (!timeout.is_zero()).then(|| time::Instant::now() + timeout);
+        loop {
+            let mut state = self.inner.state.lock().unwrap();
+            deregister(&mut state.producers);
+            if !self.is_full_at(state.items.len()) {
+                self.push(state, value);
+                return Ok(());
+            }
+            if matches!(deadline, Some(deadline) if time::Instant::now() >= deadline) {
+                return Err(PutError(value, QueueError::Full));
+            }
+            state.producers.push_back(thread::current());
+            drop(state);
+            park(deadline);
+        }
+    }
+}
+
+impl<T> Clone for ParkQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
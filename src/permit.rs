@@ -0,0 +1,166 @@
+use std::sync::atomic::Ordering;
+use std::time;
+
+use crate::queue::*;
+
+/// A slot reserved in a queue with `try_reserve`. Putting through the permit can't fail
+/// for lack of room; dropping it unused releases the slot.
+pub struct Permit<Q: BasicArray<T>, T> {
+    queue: BaseQueue<Q, T>,
+    used: bool,
+}
+
+impl<Q: BasicArray<T>, T> Permit<Q, T> {
+    /// Puts `value` into the reserved slot. The put is checked like any other except for
+    /// room, so it fails if the queue was closed, the value is refused by the queue's
+    /// priority cap or its rate limit, in which case the slot is released. A duplicate is
+    /// dropped, also releasing the slot.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(Some(1));
+    /// let permit = queue.try_reserve().unwrap();
+    /// queue.close();
+    ///
+    /// assert!(matches!(permit.put(1), Err(PutError(1, QueueError::Closed))));
+    /// assert!(queue.is_empty());
+    /// assert!(!queue.is_full());
+    /// ```
+    pub fn put(mut self, value: T) -> Result<(), PutError<T>> {
+        if self.queue.inner.is_closed() {
+            return Err(PutError(value, QueueError::Closed));
+        }
+        let mut queue = self.queue.inner.queue.lock().unwrap();
+        if queue.is_duplicate(&value) {
+            return Ok(());
+        }
+        if !queue.admits(&value) {
+            return Err(PutError(value, QueueError::PriorityCap));
+        }
+        if let Some(bucket) = &self.queue.inner.rate_limit {
+            if !bucket.lock().unwrap().try_acquire() {
+                return Err(PutError(value, QueueError::RateLimited));
+            }
+        }
+        self.queue.inner.reserved.fetch_sub(1, Ordering::SeqCst);
+        queue.put(value);
+        self.queue.inner.record_len(queue.len());
+        drop(queue);
+        self.used = true;
+        self.queue.inner.notify_not_empty();
+        Ok(())
+    }
+}
+
+impl<Q: BasicArray<T>, T> Drop for Permit<Q, T> {
+    fn drop(&mut self) {
+        if !self.used {
+            let queue = self.queue.inner.queue.lock().unwrap();
+            self.queue.inner.reserved.fetch_sub(1, Ordering::SeqCst);
+            drop(queue);
+            self.queue.inner.notify_not_full();
+        }
+    }
+}
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Reserves a slot for a later put, or returns `None` if the queue is full. Reserved
+    /// slots count toward `is_full` until they are used or released.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(2));
+    ///
+    /// let permit1 = queue.try_reserve().unwrap();
+    /// let permit2 = queue.try_reserve().unwrap();
+    /// assert!(queue.try_reserve().is_none());
+    /// assert!(queue.is_full());
+    /// assert!(queue.put(0).is_err());
+    ///
+    /// permit1.put(1).unwrap();
+    /// drop(permit2);
+    /// assert_eq!(queue.len(), 1);
+    ///
+    /// queue.put(2).unwrap();
+    /// assert_eq!(queue.get().unwrap(), 1);
+    /// assert_eq!(queue.get().unwrap(), 2);
+    /// ```
+    pub fn try_reserve(&self) -> Option<Permit<Q, T>> {
+        let queue = self.inner.queue.lock().unwrap();
+        if self.inner.is_full_at(queue.len()) {
+            return None;
+        }
+        self.inner.reserved.fetch_add(1, Ordering::SeqCst);
+        Some(Permit {
+            queue: self.clone(),
+            used: false,
+        })
+    }
+
+    /// Like `try_reserve`, but waits up to `timeout` for a slot to free up, so a producer
+    /// can secure room before computing the value to put. A zero `timeout` waits without
+    /// limit.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(Some(1));
+    /// queue.put(0).unwrap();
+    /// assert!(matches!(
+    ///     queue.reserve_wait(time::Duration::from_millis(10)),
+    ///     Err(QueueError::Full)
+    /// ));
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     thread::sleep(time::Duration::from_millis(20));
+    ///     consumer.get().unwrap()
+    /// });
+    /// let permit = queue.reserve_wait(time::Duration::from_millis(1000)).unwrap();
+    /// assert_eq!(th.join().unwrap(), 0);
+    /// assert!(queue.is_full());
+    ///
+    /// permit.put(1).unwrap();
+    /// assert_eq!(queue.get().unwrap(), 1);
+    /// ```
+    pub fn reserve_wait(&self, timeout: time::Duration) -> Result<Permit<Q, T>, QueueError> {
+        let timestamp = time::Instant::now();
+        loop {
+            if self.inner.is_shut_down() {
+                return Err(QueueError::ShutDown);
+            }
+            if self.inner.is_closed() {
+                return Err(QueueError::Closed);
+            }
+            if let Some(permit) = self.try_reserve() {
+                return Ok(permit);
+            }
+            let remaining = match timeout.is_zero() {
+                true => time::Duration::ZERO,
+                false => match timeout.checked_sub(timestamp.elapsed()) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    _ => return Err(QueueError::Full),
+                },
+            };
+            let _waiting = Waiting::new(&self.inner.waiting_producers);
+            let ready = self.inner.wait_until(
+                &self.inner.not_full,
+                || !self.is_full() || self.inner.is_closed(),
+                remaining,
+            );
+            if self.inner.is_shut_down() {
+                return Err(QueueError::ShutDown);
+            }
+            if !ready {
+                return Err(QueueError::Full);
+            }
+        }
+    }
+}
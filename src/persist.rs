@@ -0,0 +1,122 @@
+use std::io::{self, Read, Write};
+
+use crate::queue::*;
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Writes a snapshot of the queued items to `w`, encoding each item with `encode`,
+    /// and returns the number of items written. The items stay queued.
+    ///
+    /// The snapshot is the item count as a little-endian `u64`, followed by the items in an
+    /// order which `restore` puts back into the same queue order.
+    ///
+    /// # Example
+    /// ```
+    /// use std::io::{self, Read, Write};
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// fn encode(item: &String, w: &mut Vec<u8>) -> io::Result<()> {
+    ///     w.write_all(&(item.len() as u32).to_le_bytes())?;
+    ///     w.write_all(item.as_bytes())
+    /// }
+    ///
+    /// fn decode(r: &mut &[u8]) -> io::Result<String> {
+    ///     let mut len = [0; 4];
+    ///     r.read_exact(&mut len)?;
+    ///     let mut buf = vec![0; u32::from_le_bytes(len) as usize];
+    ///     r.read_exact(&mut buf)?;
+    ///     String::from_utf8(buf).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    /// }
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// queue.put("first".to_string()).unwrap();
+    /// queue.put("second".to_string()).unwrap();
+    ///
+    /// let mut buf = Vec::new();
+    /// assert_eq!(queue.persist(&mut buf, encode).unwrap(), 2);
+    /// assert_eq!(queue.len(), 2);
+    ///
+    /// let restored = FifoQueue::new(None);
+    /// assert_eq!(restored.restore(&mut buf.as_slice(), decode).unwrap(), 2);
+    /// assert_eq!(restored.get().unwrap(), "first");
+    /// assert_eq!(restored.get().unwrap(), "second");
+    /// ```
+    pub fn persist<W: Write>(
+        &self,
+        w: &mut W,
+        encode: impl Fn(&T, &mut W) -> io::Result<()>,
+    ) -> io::Result<usize> {
+        let queue = self.inner.queue.lock().unwrap();
+        w.write_all(&(queue.len() as u64).to_le_bytes())?;
+        for value in queue.iter() {
+            encode(value, w)?;
+        }
+        Ok(queue.len())
+    }
+
+    /// Reads a snapshot written by `persist`, decoding each item with `decode` and putting
+    /// it into the queue. Returns the number of items put.
+    ///
+    /// Nothing is put if the queue is closed, or if the snapshot has more items than the
+    /// queue has room for, which fails with `io::ErrorKind::Other`. Otherwise a put can
+    /// still fail partway through, e.g. on a rate limit, as can decoding an item. The items
+    /// put until then stay queued, the item which failed is lost, and the error's message
+    /// tells how many items were put.
+    ///
+    /// # Example
+    /// ```
+    /// use std::io::{self, Read, Write};
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// fn decode(r: &mut &[u8]) -> io::Result<u8> {
+    ///     let mut item = [0; 1];
+    ///     r.read_exact(&mut item)?;
+    ///     Ok(item[0])
+    /// }
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for i in 0..3 {
+    ///     queue.put(i).unwrap();
+    /// }
+    /// let mut buf = Vec::new();
+    /// queue.persist(&mut buf, |item, w| w.write_all(&[*item])).unwrap();
+    ///
+    /// let small = FifoQueue::new(Some(2));
+    /// let err = small.restore(&mut buf.as_slice(), decode).unwrap_err();
+    /// assert_eq!(err.kind(), io::ErrorKind::Other);
+    /// assert!(small.is_empty());
+    ///
+    /// let limited = FifoQueue::with_rate_limit(None, 2, time::Duration::from_secs(60));
+    /// let err = limited.restore(&mut buf.as_slice(), decode).unwrap_err();
+    /// assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    /// assert!(err.to_string().starts_with("2 of 3 items restored"));
+    /// assert_eq!(limited.len(), 2);
+    /// ```
+    pub fn restore<R: Read>(
+        &self,
+        r: &mut R,
+        decode: impl Fn(&mut R) -> io::Result<T>,
+    ) -> io::Result<usize> {
+        let mut count = [0; 8];
+        r.read_exact(&mut count)?;
+        let count = u64::from_le_bytes(count) as usize;
+        if self.inner.is_closed() {
+            return Err(QueueError::Closed.into());
+        }
+        if count > 0 {
+            let len = self.inner.queue.lock().unwrap().len();
+            if self.inner.is_full_at(len + count - 1) {
+                let message = format!("{} items don't fit in the queue", count);
+                return Err(io::Error::other(message));
+            }
+        }
+        for put in 0..count {
+            let ret = decode(r).and_then(|value| Ok(self.put(value)?));
+            if let Err(err) = ret {
+                let message = format!("{} of {} items restored: {}", put, count, err);
+                return Err(io::Error::new(err.kind(), message));
+            }
+        }
+        Ok(count)
+    }
+}
@@ -1,11 +1,45 @@
-use std::cmp::{Ord, Ordering};
+use std::cell::Cell;
+use std::cmp::{Ord, Ordering, Reverse};
 use std::collections::BinaryHeap;
+use std::sync::Arc;
 
 use crate::queue::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PrioritizedItem<T, P>(pub T, pub P);
 
+impl<T, P> PrioritizedItem<T, P> {
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{PrioritizedItem, PriorityQueue, Queue};
+    ///
+    /// let queue = PriorityQueue::new(None);
+    /// queue.put(PrioritizedItem::new("low", 1)).unwrap();
+    /// queue.put(PrioritizedItem::new("high", 2)).unwrap();
+    ///
+    /// let item = queue.get().unwrap();
+    /// assert_eq!(*item.value(), "high");
+    /// assert_eq!(*item.priority(), 2);
+    /// assert_eq!(item.clone().into_parts(), ("high", 2));
+    /// ```
+    pub fn new(value: T, priority: P) -> Self {
+        Self(value, priority)
+    }
+
+    pub fn value(&self) -> &T {
+        &self.0
+    }
+
+    pub fn priority(&self) -> &P {
+        &self.1
+    }
+
+    pub fn into_parts(self) -> (T, P) {
+        (self.0, self.1)
+    }
+}
+
 impl<T, P: Ord> Eq for PrioritizedItem<T, P> {}
 
 impl<T, P: Ord> PartialEq<Self> for PrioritizedItem<T, P> {
@@ -16,7 +50,7 @@ impl<T, P: Ord> PartialEq<Self> for PrioritizedItem<T, P> {
 
 impl<T, P: Ord> PartialOrd<Self> for PrioritizedItem<T, P> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.1.partial_cmp(&other.1)
+        Some(self.cmp(other))
     }
 }
 
@@ -26,6 +60,70 @@ impl<T, P: Ord> Ord for PrioritizedItem<T, P> {
     }
 }
 
+/// A named priority for `PrioritizedItem`, where a greater value is dequeued first.
+///
+/// # Example
+/// ```
+/// use rueue::{PrioritizedItem, Priority, PriorityQueue, Queue};
+///
+/// let queue = PriorityQueue::new(None);
+///
+/// queue.put(PrioritizedItem("cleanup", Priority::low())).unwrap();
+/// queue.put(PrioritizedItem("request", Priority::normal())).unwrap();
+/// queue.put(PrioritizedItem("alert", Priority::high())).unwrap();
+/// queue.put(PrioritizedItem("shutdown", Priority::highest())).unwrap();
+/// queue.put(PrioritizedItem("metrics", Priority::lowest())).unwrap();
+///
+/// assert_eq!(queue.get().unwrap().0, "shutdown");
+/// assert_eq!(queue.get().unwrap().0, "alert");
+/// assert_eq!(queue.get().unwrap().0, "request");
+/// assert_eq!(queue.get().unwrap().0, "cleanup");
+/// assert_eq!(queue.get().unwrap().0, "metrics");
+///
+/// assert!(Priority::high() > Priority::low());
+/// assert!(Priority::new(200) > Priority::normal());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(pub u8);
+
+impl Priority {
+    pub const fn new(value: u8) -> Self {
+        Self(value)
+    }
+
+    pub const fn lowest() -> Self {
+        Self(u8::MIN)
+    }
+
+    pub const fn low() -> Self {
+        Self(64)
+    }
+
+    pub const fn normal() -> Self {
+        Self(128)
+    }
+
+    pub const fn high() -> Self {
+        Self(192)
+    }
+
+    pub const fn highest() -> Self {
+        Self(u8::MAX)
+    }
+}
+
+impl From<Priority> for f64 {
+    fn from(priority: Priority) -> Self {
+        priority.0.into()
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Self::normal()
+    }
+}
+
 impl<T, P: Ord> BasicArray<PrioritizedItem<T, P>> for BinaryHeap<PrioritizedItem<T, P>> {
     fn new(maxsize: Option<usize>) -> Self {
         match maxsize {
@@ -45,6 +143,22 @@ impl<T, P: Ord> BasicArray<PrioritizedItem<T, P>> for BinaryHeap<PrioritizedItem
     fn put(&mut self, value: PrioritizedItem<T, P>) {
         self.push(value)
     }
+
+    fn peek(&self) -> Option<&PrioritizedItem<T, P>> {
+        BinaryHeap::peek(self)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &PrioritizedItem<T, P>> + '_> {
+        Box::new(BinaryHeap::iter(self))
+    }
+
+    fn capacity(&self) -> usize {
+        BinaryHeap::capacity(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        BinaryHeap::shrink_to_fit(self)
+    }
 }
 
 /// Queue with a priority.
@@ -53,7 +167,7 @@ impl<T, P: Ord> BasicArray<PrioritizedItem<T, P>> for BinaryHeap<PrioritizedItem
 /// ```
 /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
 ///
-/// let mut queue = PriorityQueue::new(None);
+/// let queue = PriorityQueue::new(None);
 ///
 /// queue.put(PrioritizedItem(1, 10)).unwrap();
 /// queue.put(PrioritizedItem(2, 8)).unwrap();
@@ -71,4 +185,442 @@ impl<T, P: Ord> BasicArray<PrioritizedItem<T, P>> for BinaryHeap<PrioritizedItem
 /// assert_eq!(third_item.0, 2);
 /// assert_eq!(third_item.1, 8);
 /// ```
+///
+/// A bounded queue blocks `put_wait` while it is full. Blocked producers are let in as
+/// items are taken, in the order they started waiting, but the items they put are still
+/// taken by priority.
+/// ```
+/// use std::thread;
+/// use std::time;
+/// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+///
+/// let queue = PriorityQueue::new(Some(2));
+/// queue.put(PrioritizedItem("queued", 5)).unwrap();
+/// queue.put(PrioritizedItem("queued", 6)).unwrap();
+///
+/// let producers: Vec<_> = [1, 9]
+///     .into_iter()
+///     .map(|priority| {
+///         let producer = queue.clone();
+///         let item = PrioritizedItem("blocked", priority);
+///         thread::spawn(move || producer.put_wait(item, time::Duration::from_millis(5000)))
+///     })
+///     .collect();
+/// while queue.waiting_producers() < 2 {
+///     thread::sleep(time::Duration::from_millis(1));
+/// }
+/// assert_eq!(queue.len(), 2);
+///
+/// assert_eq!(queue.get().unwrap().1, 6);
+/// while queue.waiting_producers() > 1 || !queue.is_full() {
+///     thread::sleep(time::Duration::from_millis(1));
+/// }
+/// let second = queue.get().unwrap().1;
+/// for producer in producers {
+///     producer.join().unwrap().unwrap();
+/// }
+///
+/// // Whichever producer got in first, the items left behind come out by priority.
+/// let third = queue.get().unwrap().1;
+/// let fourth = queue.get().unwrap().1;
+/// assert!(third > fourth);
+///
+/// let mut taken = vec![second, third, fourth];
+/// taken.sort();
+/// assert_eq!(taken, vec![1, 5, 9]);
+/// assert!(queue.is_empty());
+/// ```
 pub type PriorityQueue<T, P> = BaseQueue<BinaryHeap<PrioritizedItem<T, P>>, PrioritizedItem<T, P>>;
+
+impl<T: Clone, P: Ord + Clone> PriorityQueue<T, P> {
+    /// Iterates over clones of the queued items in the order `get` would return them,
+    /// leaving the queue intact.
+    ///
+    /// The whole heap is cloned up front under the lock, so this costs O(n) clones plus
+    /// O(n log n) to drain the copy.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// let queue = PriorityQueue::new(None);
+    ///
+    /// queue.put(PrioritizedItem(1, 10)).unwrap();
+    /// queue.put(PrioritizedItem(2, 8)).unwrap();
+    /// queue.put(PrioritizedItem(3, 9)).unwrap();
+    ///
+    /// let items: Vec<_> = queue.iter_by_priority().map(|item| item.0).collect();
+    /// assert_eq!(items, vec![1, 3, 2]);
+    /// assert_eq!(queue.len(), 3);
+    ///
+    /// for item in items {
+    ///     assert_eq!(queue.get().unwrap().0, item);
+    /// }
+    /// ```
+    pub fn iter_by_priority(&self) -> impl Iterator<Item = PrioritizedItem<T, P>> {
+        let mut heap: BinaryHeap<_> = self.inner.queue.lock().unwrap().iter().cloned().collect();
+        std::iter::from_fn(move || heap.pop())
+    }
+
+    /// Returns clones of the `n` highest priority items, highest first, leaving the queue
+    /// intact.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// let queue = PriorityQueue::new(None);
+    /// for (value, priority) in [("a", 3), ("b", 7), ("c", 1), ("d", 5)] {
+    ///     queue.put(PrioritizedItem(value, priority)).unwrap();
+    /// }
+    ///
+    /// let top: Vec<_> = queue.nlargest(2).into_iter().map(|item| item.0).collect();
+    /// assert_eq!(top, vec!["b", "d"]);
+    /// assert_eq!(queue.nlargest(10).len(), 4);
+    /// assert_eq!(queue.len(), 4);
+    /// ```
+    pub fn nlargest(&self, n: usize) -> Vec<PrioritizedItem<T, P>> {
+        let mut heap = self.inner.queue.lock().unwrap().clone();
+        std::iter::from_fn(|| heap.pop()).take(n).collect()
+    }
+
+    /// Returns clones of the `n` lowest priority items, lowest first, leaving the queue
+    /// intact.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// let queue = PriorityQueue::new(None);
+    /// for (value, priority) in [("a", 3), ("b", 7), ("c", 1), ("d", 5)] {
+    ///     queue.put(PrioritizedItem(value, priority)).unwrap();
+    /// }
+    ///
+    /// let bottom: Vec<_> = queue.nsmallest(3).into_iter().map(|item| item.0).collect();
+    /// assert_eq!(bottom, vec!["c", "a", "d"]);
+    /// assert_eq!(queue.get().unwrap().0, "b");
+    /// ```
+    pub fn nsmallest(&self, n: usize) -> Vec<PrioritizedItem<T, P>> {
+        let mut items = self.inner.queue.lock().unwrap().clone().into_vec();
+        items.sort();
+        items.truncate(n);
+        items
+    }
+}
+
+impl<T, P: Ord> PriorityQueue<T, P> {
+    /// Creates a queue holding `items`, heapifying them in O(n) instead of putting them
+    /// one by one in O(n log n). If there are more items than `maxsize`, only the
+    /// `maxsize` highest priority ones are kept and the rest are dropped.
+    ///
+    /// # Example
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// static COMPARISONS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// #[derive(Debug, PartialEq, Eq, PartialOrd)]
+    /// struct Counted(u32);
+    ///
+    /// impl Ord for Counted {
+    ///     fn cmp(&self, other: &Self) -> Ordering {
+    ///         COMPARISONS.fetch_add(1, AtomicOrdering::SeqCst);
+    ///         self.0.cmp(&other.0)
+    ///     }
+    /// }
+    ///
+    /// let items = || (0..10_000).map(|i| PrioritizedItem(i, Counted(i)));
+    ///
+    /// let pushed = PriorityQueue::new(None);
+    /// for item in items() {
+    ///     pushed.put(item).unwrap();
+    /// }
+    /// let push_comparisons = COMPARISONS.swap(0, AtomicOrdering::SeqCst);
+    ///
+    /// let queue = PriorityQueue::from_prioritized(items().collect(), None);
+    /// let heapify_comparisons = COMPARISONS.swap(0, AtomicOrdering::SeqCst);
+    /// assert!(heapify_comparisons < push_comparisons);
+    ///
+    /// assert_eq!(queue.len(), 10_000);
+    /// for i in (0..10_000).rev() {
+    ///     assert_eq!(queue.get().unwrap().0, i);
+    /// }
+    ///
+    /// let queue = PriorityQueue::from_prioritized(vec![
+    ///     PrioritizedItem("a", 1),
+    ///     PrioritizedItem("b", 3),
+    ///     PrioritizedItem("c", 2),
+    /// ], Some(2));
+    /// assert!(queue.is_full());
+    /// assert_eq!(queue.get().unwrap().0, "b");
+    /// assert_eq!(queue.get().unwrap().0, "c");
+    /// ```
+    pub fn from_prioritized(mut items: Vec<PrioritizedItem<T, P>>, maxsize: Option<usize>) -> Self {
+        if let Some(maxsize) = maxsize {
+            if items.len() > maxsize {
+                if maxsize > 0 {
+                    items.select_nth_unstable_by(maxsize - 1, |a, b| b.cmp(a));
+                }
+                items.truncate(maxsize);
+            }
+        }
+        Self {
+            inner: Arc::new(QueueInner::with_array(BinaryHeap::from(items), maxsize)),
+        }
+    }
+
+    /// Restores the heap order after the priorities of queued items have changed in place,
+    /// e.g. through interior mutability. Without it, such items are taken in an
+    /// unspecified order. Runs in O(n).
+    ///
+    /// # Example
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// let queue = PriorityQueue::new(None);
+    /// let priorities: Vec<_> = (0..5).map(|i| Rc::new(Cell::new(i))).collect();
+    /// for (i, priority) in priorities.iter().enumerate() {
+    ///     queue.put(PrioritizedItem(i, Rc::clone(priority))).unwrap();
+    /// }
+    ///
+    /// for priority in &priorities {
+    ///     priority.set(10 - priority.get());
+    /// }
+    /// queue.rebuild();
+    ///
+    /// for i in 0..5 {
+    ///     assert_eq!(queue.get().unwrap().0, i);
+    /// }
+    /// ```
+    pub fn rebuild(&self) {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let items = std::mem::take(&mut *queue).into_vec();
+        *queue = BinaryHeap::from(items);
+    }
+
+    /// Removes and returns an item whose value equals `target`, whatever its priority.
+    /// The heap is rebuilt afterwards, in O(n).
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// let queue = PriorityQueue::new(None);
+    /// queue.put(PrioritizedItem("a", 3)).unwrap();
+    /// queue.put(PrioritizedItem("b", 2)).unwrap();
+    /// queue.put(PrioritizedItem("c", 1)).unwrap();
+    ///
+    /// let item = queue.take(&"b").unwrap();
+    /// assert_eq!((item.0, item.1), ("b", 2));
+    /// assert!(queue.take(&"d").is_none());
+    ///
+    /// assert_eq!(queue.get().unwrap().0, "a");
+    /// assert_eq!(queue.get().unwrap().0, "c");
+    /// ```
+    pub fn take(&self, target: &T) -> Option<PrioritizedItem<T, P>>
+    where
+        T: PartialEq,
+    {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let mut items = std::mem::take(&mut *queue).into_vec();
+        let index = items[..].iter().position(|item| item.0 == *target);
+        let value = index.map(|index| items.swap_remove(index));
+        *queue = BinaryHeap::from(items);
+        if value.is_some() {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        if value.is_some() {
+            self.inner.notify_not_full();
+        }
+        value
+    }
+
+    /// Takes every item matching `pred`, returning them in the order they would have been
+    /// taken, and leaves the others queued. The heap is rebuilt afterwards, in O(n).
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// let queue = PriorityQueue::new(None);
+    /// for i in 0..6 {
+    ///     queue.put(PrioritizedItem(i, i)).unwrap();
+    /// }
+    ///
+    /// let odd: Vec<_> = queue.get_matching(|item| item.0 % 2 == 1).into_iter().map(|item| item.0).collect();
+    /// assert_eq!(odd, vec![5, 3, 1]);
+    /// for i in [4, 2, 0] {
+    ///     assert_eq!(queue.get().unwrap().0, i);
+    /// }
+    /// ```
+    pub fn get_matching(
+        &self,
+        mut pred: impl FnMut(&PrioritizedItem<T, P>) -> bool,
+    ) -> Vec<PrioritizedItem<T, P>> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let (mut matched, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut *queue)
+            .into_vec()
+            .into_iter()
+            .partition(|item| pred(item));
+        *queue = BinaryHeap::from(kept);
+        if !matched.is_empty() {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        if !matched.is_empty() {
+            self.inner.notify_all_not_full();
+        }
+        matched.sort_by(|a, b| b.cmp(a));
+        matched
+    }
+
+    /// Takes every item whose priority lies between `lo` and `hi`, both included, and
+    /// returns them in the order they would have been taken. The heap is rebuilt
+    /// afterwards, in O(n).
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// let queue = PriorityQueue::new(None);
+    /// for (value, priority) in [("a", 5), ("b", 1), ("c", 3), ("d", 7), ("e", 4), ("f", 2)] {
+    ///     queue.put(PrioritizedItem(value, priority)).unwrap();
+    /// }
+    ///
+    /// let drained: Vec<_> = queue.drain_priority_range(2, 4).into_iter().map(|item| item.1).collect();
+    /// assert_eq!(drained, vec![4, 3, 2]);
+    /// for priority in [7, 5, 1] {
+    ///     assert_eq!(queue.get().unwrap().1, priority);
+    /// }
+    /// assert!(queue.drain_priority_range(0, 10).is_empty());
+    /// ```
+    pub fn drain_priority_range(&self, lo: P, hi: P) -> Vec<PrioritizedItem<T, P>> {
+        self.get_matching(|item| lo <= item.1 && item.1 <= hi)
+    }
+
+    /// Applies `f` to every queued item in place, without taking any. Since `f` may change
+    /// priorities, the heap is rebuilt afterwards, in O(n).
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// let queue = PriorityQueue::new(None);
+    /// queue.put(PrioritizedItem("a", 3)).unwrap();
+    /// queue.put(PrioritizedItem("b", 2)).unwrap();
+    /// queue.put(PrioritizedItem("c", 1)).unwrap();
+    ///
+    /// queue.map_in_place(|item| item.1 = -item.1);
+    /// assert_eq!(queue.get().unwrap().0, "c");
+    /// assert_eq!(queue.get().unwrap().0, "b");
+    /// assert_eq!(queue.get().unwrap().0, "a");
+    /// ```
+    pub fn map_in_place(&self, f: impl FnMut(&mut PrioritizedItem<T, P>)) {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let mut items = std::mem::take(&mut *queue).into_vec();
+        items.iter_mut().for_each(f);
+        *queue = BinaryHeap::from(items);
+    }
+
+    /// Takes the highest priority item only if `pred` accepts its value and priority,
+    /// leaving the queue untouched otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{PriorityQueue, PrioritizedItem, Queue};
+    ///
+    /// let queue = PriorityQueue::new(None);
+    /// queue.put(PrioritizedItem("urgent", 9)).unwrap();
+    /// queue.put(PrioritizedItem("routine", 1)).unwrap();
+    ///
+    /// let item = queue.pop_if(|_, priority| *priority > 5).unwrap();
+    /// assert_eq!(item.0, "urgent");
+    ///
+    /// assert!(queue.pop_if(|_, priority| *priority > 5).is_none());
+    /// assert_eq!(queue.len(), 1);
+    /// assert!(queue.pop_if(|value, _| *value == "routine").is_some());
+    /// assert!(queue.pop_if(|_, _| true).is_none());
+    /// ```
+    pub fn pop_if(&self, pred: impl FnOnce(&T, &P) -> bool) -> Option<PrioritizedItem<T, P>> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let top = queue.peek()?;
+        if !pred(&top.0, &top.1) {
+            return None;
+        }
+        let value = queue.pop();
+        self.inner.record_len(queue.len());
+        drop(queue);
+        self.inner.notify_not_full();
+        value
+    }
+}
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Takes every queued item and returns them in a new priority queue with the same
+    /// bound, prioritized by `key`. Other handles to this queue remain usable, and see it empty.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(4));
+    /// for i in [3, 1, 4, 2] {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// let priority = queue.into_priority(|i| *i);
+    /// assert!(priority.is_full());
+    /// for i in (1..=4).rev() {
+    ///     assert_eq!(priority.get().unwrap().0, i);
+    /// }
+    /// ```
+    pub fn into_priority<P: Ord>(self, key: impl Fn(&T) -> P) -> PriorityQueue<T, P> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let items: Vec<_> = std::iter::from_fn(|| queue.get())
+            .map(|value| {
+                let priority = key(&value);
+                PrioritizedItem(value, priority)
+            })
+            .collect();
+        if !items.is_empty() {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        self.inner.notify_all_not_full();
+        BaseQueue {
+            inner: Arc::new(QueueInner::with_array(
+                BinaryHeap::from(items),
+                self.inner.maxsize(),
+            )),
+        }
+    }
+
+    /// Like `into_priority`, but prioritizes items by the order they would have been
+    /// taken in, so the new queue hands them out in the same order.
+    ///
+    /// # Example
+    /// ```
+    /// use std::cmp::Reverse;
+    /// use rueue::{LifoQueue, Queue};
+    ///
+    /// let queue = LifoQueue::new(None);
+    /// for word in ["a", "b", "c"] {
+    ///     queue.put(word).unwrap();
+    /// }
+    ///
+    /// let priority = queue.into_fifo_priority();
+    /// assert_eq!(priority.get().unwrap().into_parts(), ("c", Reverse(0)));
+    /// assert_eq!(priority.get().unwrap().0, "b");
+    /// assert_eq!(priority.get().unwrap().0, "a");
+    /// ```
+    pub fn into_fifo_priority(self) -> PriorityQueue<T, Reverse<u64>> {
+        // `into_priority` keys the items in the order it takes them.
+        let sequence = Cell::new(0);
+        self.into_priority(|_| Reverse(sequence.replace(sequence.get() + 1)))
+    }
+}
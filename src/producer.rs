@@ -0,0 +1,39 @@
+use std::sync::atomic::Ordering;
+use std::time;
+
+use crate::queue::*;
+
+/// A put-only handle to a queue. The queue is closed when the last producer is dropped,
+/// so consumers observe `QueueError::Closed` without an explicit `close()`.
+pub struct Producer<Q: BasicArray<T>, T> {
+    queue: BaseQueue<Q, T>,
+}
+
+impl<Q: BasicArray<T>, T> Producer<Q, T> {
+    pub(crate) fn new(queue: BaseQueue<Q, T>) -> Self {
+        queue.inner.producers.fetch_add(1, Ordering::SeqCst);
+        Self { queue }
+    }
+
+    pub fn put(&self, value: T) -> Result<(), PutError<T>> {
+        self.queue.put(value)
+    }
+
+    pub fn put_wait(&self, value: T, timeout: time::Duration) -> Result<(), PutError<T>> {
+        self.queue.put_wait(value, timeout)
+    }
+}
+
+impl<Q: BasicArray<T>, T> Clone for Producer<Q, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.queue.clone())
+    }
+}
+
+impl<Q: BasicArray<T>, T> Drop for Producer<Q, T> {
+    fn drop(&mut self) {
+        if self.queue.inner.producers.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.queue.inner.close();
+        }
+    }
+}
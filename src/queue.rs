@@ -1,23 +1,239 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::io;
+use std::iter::Peekable;
 use std::marker::PhantomData;
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard, TryLockError};
+use std::thread;
 use std::time;
 
-#[derive(Debug)]
+#[cfg(feature = "audit")]
+use crate::audit::{AuditOp, AuditRing};
+#[cfg(feature = "histogram")]
+use crate::histogram::LengthHistogram;
+use crate::producer::Producer;
+use crate::queue_group::GroupSignal;
+use crate::rate_limited_queue::TokenBucket;
+
+/// Emits a `tracing` event with the `tracing` feature, and compiles to nothing without it.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*);
+    };
+}
+
+/// Records an operation in the audit log with the `audit` feature, and compiles to nothing
+/// without it.
+macro_rules! audit_event {
+    ($inner:expr, $op:expr, $len:expr) => {
+        #[cfg(feature = "audit")]
+        $inner.audit.record($op, $len);
+    };
+}
+
+/// Why a queue operation failed.
+///
+/// Errors compare equal when they are the same variant; should a variant carry data,
+/// that data is compared as well.
+///
+/// # Example
+/// ```
+/// use rueue::{FifoQueue, Queue, QueueError};
+///
+/// let queue: FifoQueue<i32> = FifoQueue::new(None);
+/// assert_eq!(queue.get().unwrap_err(), QueueError::Empty);
+/// assert_ne!(QueueError::Empty, QueueError::Full);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum QueueError {
     Full,
     Empty,
+    RateLimited,
+    Closed,
+    PriorityCap,
+    Cancelled,
+    ShutDown,
+}
+
+impl QueueError {
+    /// Whether retrying the operation later may succeed.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::QueueError;
+    ///
+    /// assert!(QueueError::Full.is_transient());
+    /// assert!(QueueError::Empty.is_transient());
+    /// assert!(QueueError::RateLimited.is_transient());
+    /// assert!(QueueError::PriorityCap.is_transient());
+    /// assert!(QueueError::Cancelled.is_transient());
+    /// assert!(!QueueError::Closed.is_transient());
+    /// assert!(!QueueError::ShutDown.is_transient());
+    /// ```
+    pub fn is_transient(&self) -> bool {
+        match self {
+            QueueError::Full
+            | QueueError::Empty
+            | QueueError::RateLimited
+            | QueueError::PriorityCap
+            | QueueError::Cancelled => true,
+            QueueError::Closed | QueueError::ShutDown => false,
+        }
+    }
+
+    /// Whether the queue will never accept the operation again.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::QueueError;
+    ///
+    /// assert!(!QueueError::Full.is_terminal());
+    /// assert!(!QueueError::Empty.is_terminal());
+    /// assert!(!QueueError::RateLimited.is_terminal());
+    /// assert!(!QueueError::PriorityCap.is_terminal());
+    /// assert!(!QueueError::Cancelled.is_terminal());
+    /// assert!(QueueError::Closed.is_terminal());
+    /// assert!(QueueError::ShutDown.is_terminal());
+    /// ```
+    pub fn is_terminal(&self) -> bool {
+        !self.is_transient()
+    }
+}
+
+/// A failed put, handing back the value which was not put.
+///
+/// # Example
+/// ```
+/// use rueue::{FifoQueue, PutError, Queue, QueueError};
+///
+/// let queue = FifoQueue::new(Some(1));
+/// queue.put(1).unwrap();
+/// assert_eq!(queue.put(2).unwrap_err(), PutError(2, QueueError::Full));
+/// assert_ne!(queue.put(3).unwrap_err(), PutError(2, QueueError::Full));
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct PutError<T>(pub T, pub QueueError);
+
+/// The result of a successful put which reports more than success.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PutOutcome {
+    /// The item was put.
+    Ok,
+    /// The item was put, and the queue is now above the requested watermark.
+    OkNearFull,
+    /// The item was dropped, because an item with the same key is already queued.
+    Duplicate,
+}
+
+/// Converts a `QueueError` into an `io::Error`, so queue operations can be
+/// propagated with `?` from functions returning `io::Result`.
+///
+/// # Example
+/// ```
+/// use std::io;
+/// use rueue::QueueError;
+///
+/// let err: io::Error = QueueError::Empty.into();
+/// assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+///
+/// let err: io::Error = QueueError::Full.into();
+/// assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+///
+/// let err: io::Error = QueueError::Cancelled.into();
+/// assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+///
+/// let err: io::Error = QueueError::Closed.into();
+/// assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+/// ```
+impl From<QueueError> for io::Error {
+    fn from(err: QueueError) -> Self {
+        let kind = match err {
+            QueueError::Full
+            | QueueError::Empty
+            | QueueError::RateLimited
+            | QueueError::PriorityCap => io::ErrorKind::WouldBlock,
+            QueueError::Cancelled => io::ErrorKind::Interrupted,
+            QueueError::Closed | QueueError::ShutDown => io::ErrorKind::BrokenPipe,
+        };
+        io::Error::new(kind, format!("{:?}", err))
+    }
+}
+
+/// Converts a `PutError` into an `io::Error`, dropping the rejected value.
+///
+/// # Example
+/// ```
+/// use std::io;
+/// use rueue::{FifoQueue, Queue};
+///
+/// fn produce(queue: &FifoQueue<i32>) -> io::Result<()> {
+///     queue.put(1)?;
+///     queue.put(2)?;
+///     Ok(())
+/// }
+///
+/// let queue = FifoQueue::new(Some(1));
+/// let err = produce(&queue).unwrap_err();
+/// assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+/// ```
+impl<T> From<PutError<T>> for io::Error {
+    fn from(err: PutError<T>) -> Self {
+        err.1.into()
+    }
+}
+
+/// A failed non-blocking put, handing back the value which was not put.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryPutError<T> {
+    /// The put would have to wait, because the queue is full or otherwise not accepting
+    /// items right now.
+    Full(T),
+    /// The queue is closed and will never accept the value.
+    Closed(T),
 }
 
-#[derive(Debug)]
-pub struct PutError<T>(T, QueueError);
+impl<T> TryPutError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            TryPutError::Full(value) | TryPutError::Closed(value) => value,
+        }
+    }
+}
 
+/// Operations shared by every queue. All of them take `&self`, so one handle can be
+/// shared between threads without cloning it.
+///
+/// # Example
+/// ```
+/// use std::thread;
+/// use std::time;
+/// use rueue::{FifoQueue, Queue};
+///
+/// let queue = FifoQueue::new(Some(4));
+///
+/// thread::scope(|s| {
+///     s.spawn(|| {
+///         for i in 0..100 {
+///             queue.put_wait(i, time::Duration::from_millis(1000)).unwrap();
+///         }
+///     });
+///     s.spawn(|| {
+///         for i in 0..100 {
+///             assert_eq!(queue.get_wait(time::Duration::from_millis(1000)).unwrap(), i);
+///         }
+///     });
+/// });
+/// assert!(queue.is_empty());
+/// ```
 pub trait Queue<T> {
     ///
     /// # Example
     /// ```
     /// use rueue::{FifoQueue, Queue};
     ///
-    /// let mut queue = FifoQueue::new(None);
+    /// let queue = FifoQueue::new(None);
     /// queue.put(1).unwrap();
     ///
     /// assert_eq!(queue.len(), 1);
@@ -29,7 +245,7 @@ pub trait Queue<T> {
     /// ```
     /// use rueue::{FifoQueue, Queue};
     ///
-    /// let mut queue = FifoQueue::new(None);
+    /// let queue = FifoQueue::new(None);
     ///
     /// assert_eq!(queue.is_empty(), true);
     ///
@@ -43,27 +259,92 @@ pub trait Queue<T> {
     /// ```
     /// use rueue::{FifoQueue, Queue};
     ///
-    /// let mut queue = FifoQueue::new(Some(1));
+    /// let queue = FifoQueue::new(Some(1));
     ///
     /// assert_eq!(queue.is_full(), false);
     ///
     /// queue.put(1).unwrap();
     /// assert_eq!(queue.is_full(), true);
     /// ```
+    ///
+    /// An unbounded queue is never full.
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    ///
+    /// for i in 0..1000 {
+    ///     queue.put(i).unwrap();
+    ///     assert_eq!(queue.is_full(), false);
+    /// }
+    /// ```
     fn is_full(&self) -> bool;
 
+    /// Whether the queue has a maxsize.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue: FifoQueue<i32> = FifoQueue::new(Some(1));
+    /// assert_eq!(queue.is_bounded(), true);
+    ///
+    /// let queue: FifoQueue<i32> = FifoQueue::new(None);
+    /// assert_eq!(queue.is_bounded(), false);
+    /// ```
+    fn is_bounded(&self) -> bool;
+
+    /// The current bound, or `None` if the queue is unbounded.
+    ///
+    /// A put checks fullness and inserts under the same lock a resize takes, so the queue
+    /// never holds more items than the bound in effect at that moment.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(16));
+    /// assert_eq!(queue.maxsize(), Some(16));
+    ///
+    /// thread::scope(|s| {
+    ///     for _ in 0..4 {
+    ///         s.spawn(|| {
+    ///             for i in 0..2000 {
+    ///                 let _ = queue.put(i);
+    ///                 let _ = queue.get();
+    ///             }
+    ///         });
+    ///     }
+    ///     s.spawn(|| {
+    ///         for maxsize in (1..16).rev() {
+    ///             queue.set_maxsize_evict(maxsize);
+    ///             for _ in 0..100 {
+    ///                 assert!(queue.len() <= maxsize);
+    ///                 assert_eq!(queue.maxsize(), Some(maxsize));
+    ///             }
+    ///         }
+    ///     });
+    /// });
+    /// assert!(queue.len() <= 1);
+    ///
+    /// let queue: FifoQueue<i32> = FifoQueue::new(None);
+    /// assert_eq!(queue.maxsize(), None);
+    /// ```
+    fn maxsize(&self) -> Option<usize>;
+
     ///
     /// # Example
     /// ```
     /// use rueue::{FifoQueue, Queue};
     ///
-    /// let mut queue = FifoQueue::new(None);
+    /// let queue = FifoQueue::new(None);
     ///
     /// queue.put(1).unwrap();
     /// let item = queue.get().unwrap();
     /// assert_eq!(item, 1);
     /// ```
-    fn get(&mut self) -> Result<T, QueueError>;
+    fn get(&self) -> Result<T, QueueError>;
 
     ///
     /// # Example
@@ -71,26 +352,88 @@ pub trait Queue<T> {
     /// use std::time;
     /// use rueue::{FifoQueue, Queue};
     ///
-    /// let mut queue = FifoQueue::new(None);
+    /// let queue = FifoQueue::new(None);
     ///
     /// queue.put(1).unwrap();
     /// let item = queue.get_wait(time::Duration::from_millis(1000)).unwrap();
     /// assert_eq!(item, 1);
     /// ```
-    fn get_wait(&mut self, timeout: time::Duration) -> Result<T, QueueError>;
+    ///
+    /// A zero `timeout` waits without limit. Wakeups which still find the queue empty,
+    /// e.g. because another consumer took the item first, back off before waiting again,
+    /// for longer after each, up to about a millisecond.
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     let timestamp = time::Instant::now();
+    ///     let mut wakeups = Vec::new();
+    ///     let ret = consumer.get_wait_observed(time::Duration::ZERO, |_| {
+    ///         wakeups.push(time::Instant::now())
+    ///     });
+    ///     (ret, wakeups, timestamp.elapsed())
+    /// });
+    ///
+    /// // Steal the items from under the consumer for a while.
+    /// let storm = time::Instant::now();
+    /// let mut stolen = 0;
+    /// while storm.elapsed() < time::Duration::from_millis(100) {
+    ///     queue.put(0).unwrap();
+    ///     if queue.get().is_ok() {
+    ///         stolen += 1;
+    ///     }
+    /// }
+    /// queue.put(1).unwrap();
+    ///
+    /// let (ret, wakeups, elapsed) = th.join().unwrap();
+    /// assert!(ret.is_ok());
+    /// // From the third fruitless wakeup on, each is followed by a sleep doubling up to
+    /// // 1024µs, which bounds how often the consumer retakes the lock.
+    /// for (i, pair) in wakeups.windows(2).enumerate().skip(1) {
+    ///     let backoff = time::Duration::from_micros(1 << (i + 1).min(10));
+    ///     assert!(pair[1] - pair[0] >= backoff);
+    /// }
+    /// assert!(wakeups.len() as u128 <= 11 + elapsed.as_micros() / 1024);
+    /// assert!(stolen > wakeups.len());
+    ///
+    /// let timestamp = time::Instant::now();
+    /// queue.put(101).unwrap();
+    /// let _ = queue.get_wait(time::Duration::ZERO).unwrap();
+    /// assert!(timestamp.elapsed() < time::Duration::from_millis(100));
+    /// ```
+    ///
+    /// Very short timeouts are honored closely rather than rounded up to a scheduler tick.
+    /// ```
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue: FifoQueue<i32> = FifoQueue::new(None);
+    ///
+    /// let timestamp = time::Instant::now();
+    /// assert!(queue.get_wait(time::Duration::from_micros(500)).is_err());
+    /// let elapsed = timestamp.elapsed();
+    /// assert!(elapsed >= time::Duration::from_micros(500));
+    /// assert!(elapsed < time::Duration::from_millis(10));
+    /// ```
+    fn get_wait(&self, timeout: time::Duration) -> Result<T, QueueError>;
 
     ///
     /// # Example
     /// ```
     /// use rueue::{FifoQueue, Queue};
     ///
-    /// let mut queue = FifoQueue::new(None);
+    /// let queue = FifoQueue::new(None);
     ///
     /// queue.put(1).unwrap();
     /// let item = queue.get().unwrap();
     /// assert_eq!(item, 1);
     /// ```
-    fn put(&mut self, value: T) -> Result<(), PutError<T>>;
+    fn put(&self, value: T) -> Result<(), PutError<T>>;
 
     ///
     /// # Example
@@ -98,13 +441,167 @@ pub trait Queue<T> {
     /// use std::time;
     /// use rueue::{FifoQueue, Queue};
     ///
-    /// let mut queue = FifoQueue::new(None);
+    /// let queue = FifoQueue::new(None);
     ///
     /// queue.put_wait(1, time::Duration::from_millis(1000)).unwrap();
     /// let item = queue.get().unwrap();
     /// assert_eq!(item, 1);
     /// ```
-    fn put_wait(&mut self, value: T, timeout: time::Duration) -> Result<(), PutError<T>>;
+    ///
+    /// The timeout is a deadline for the whole put, including taking the queue lock.
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(None);
+    ///
+    /// let holder = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     holder.put_from_iter((0..1).map(|i| {
+    ///         thread::sleep(time::Duration::from_millis(300));
+    ///         i
+    ///     }));
+    /// });
+    /// thread::sleep(time::Duration::from_millis(50));
+    ///
+    /// let timestamp = time::Instant::now();
+    /// let ret = queue.put_wait(1, time::Duration::from_millis(50));
+    /// assert!(matches!(ret, Err(PutError(1, QueueError::Full))));
+    /// assert!(timestamp.elapsed() < time::Duration::from_millis(200));
+    /// th.join().unwrap();
+    /// ```
+    ///
+    /// A put into a full queue fails once `timeout` has passed, and succeeds as soon as
+    /// room frees up before that.
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(Some(1));
+    /// queue.put(0).unwrap();
+    ///
+    /// let timestamp = time::Instant::now();
+    /// let ret = queue.put_wait(1, time::Duration::from_millis(50));
+    /// assert_eq!(ret, Err(PutError(1, QueueError::Full)));
+    /// assert!(timestamp.elapsed() >= time::Duration::from_millis(50));
+    /// assert!(timestamp.elapsed() < time::Duration::from_millis(500));
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     thread::sleep(time::Duration::from_millis(50));
+    ///     consumer.get().unwrap()
+    /// });
+    /// let timestamp = time::Instant::now();
+    /// queue.put_wait(1, time::Duration::from_millis(5000)).unwrap();
+    /// assert!(timestamp.elapsed() < time::Duration::from_millis(2500));
+    /// assert_eq!(th.join().unwrap(), 0);
+    /// ```
+    fn put_wait(&self, value: T, timeout: time::Duration) -> Result<(), PutError<T>>;
+
+    /// Whether the queue holds at least `n` items.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, LifoQueue, ParkQueue, PrioritizedItem, PriorityQueue, Queue};
+    ///
+    /// let fifo = FifoQueue::new(None);
+    /// let lifo = LifoQueue::new(None);
+    /// let priority = PriorityQueue::new(None);
+    /// let park = ParkQueue::new(None);
+    /// for i in 0..3 {
+    ///     fifo.put(i).unwrap();
+    ///     lifo.put(i).unwrap();
+    ///     priority.put(PrioritizedItem(i, i)).unwrap();
+    ///     park.put(i).unwrap();
+    /// }
+    ///
+    /// assert!(fifo.len_at_least(0) && fifo.len_at_least(3) && !fifo.len_at_least(4));
+    /// assert!(lifo.len_at_least(0) && lifo.len_at_least(3) && !lifo.len_at_least(4));
+    /// assert!(priority.len_at_least(3) && !priority.len_at_least(4));
+    /// assert!(park.len_at_least(3) && !park.len_at_least(4));
+    /// ```
+    fn len_at_least(&self, n: usize) -> bool {
+        self.len() >= n
+    }
+
+    /// Whether the queue holds at most `n` items.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, LifoQueue, ParkQueue, PrioritizedItem, PriorityQueue, Queue};
+    ///
+    /// let fifo = FifoQueue::new(None);
+    /// let lifo = LifoQueue::new(None);
+    /// let priority = PriorityQueue::new(None);
+    /// let park = ParkQueue::new(None);
+    /// assert!(fifo.len_at_most(0));
+    /// for i in 0..3 {
+    ///     fifo.put(i).unwrap();
+    ///     lifo.put(i).unwrap();
+    ///     priority.put(PrioritizedItem(i, i)).unwrap();
+    ///     park.put(i).unwrap();
+    /// }
+    ///
+    /// assert!(fifo.len_at_most(3) && !fifo.len_at_most(2));
+    /// assert!(lifo.len_at_most(3) && !lifo.len_at_most(2));
+    /// assert!(priority.len_at_most(3) && !priority.len_at_most(2));
+    /// assert!(park.len_at_most(3) && !park.len_at_most(2));
+    /// ```
+    fn len_at_most(&self, n: usize) -> bool {
+        self.len() <= n
+    }
+}
+
+/// A queue of any kind behind dynamic dispatch, e.g. to keep different implementations
+/// in one collection.
+///
+/// # Example
+/// ```
+/// use std::thread;
+/// use std::time;
+/// use rueue::{BoxedQueue, FifoQueue, PrioritizedItem, PriorityQueue, Queue};
+///
+/// let queues: Vec<BoxedQueue<PrioritizedItem<&str, i32>>> = vec![
+///     Box::new(FifoQueue::new(Some(2))),
+///     Box::new(PriorityQueue::new(Some(2))),
+/// ];
+/// for queue in &queues {
+///     queue.put(PrioritizedItem("low", 1)).unwrap();
+///     queue.put_wait(PrioritizedItem("high", 2), time::Duration::ZERO).unwrap();
+///     assert!(queue.is_full());
+///     assert_eq!(queue.maxsize(), Some(2));
+/// }
+///
+/// assert_eq!(queues[0].get().unwrap().0, "low");
+/// assert_eq!(queues[1].get().unwrap().0, "high");
+///
+/// let queue = queues.into_iter().next().unwrap();
+/// let th = thread::spawn(move || queue.get_wait(time::Duration::from_millis(1000)));
+/// assert_eq!(th.join().unwrap().unwrap().0, "high");
+/// ```
+pub type BoxedQueue<T> = Box<dyn Queue<T> + Send + Sync>;
+
+/// Timeouts shorter than this are waited out by polling, since `Condvar::wait_timeout`
+/// may overshoot them by a whole scheduler tick on some platforms.
+const SHORT_WAIT: time::Duration = time::Duration::from_millis(2);
+
+/// How many times a put with a deadline retries a contended queue lock by yielding, before
+/// it sleeps between retries instead.
+const LOCK_SPINS: u32 = 64;
+
+/// The longest sleep between retries of a contended queue lock.
+const LOCK_RETRY_MAX: time::Duration = time::Duration::from_millis(1);
+
+/// Backs off after `fruitless` consecutive wakeups which found nothing to do, so that
+/// spurious or stolen wakeups don't hammer the queue lock.
+fn backoff(fruitless: u32) {
+    if fruitless <= 1 {
+        thread::yield_now();
+    } else {
+        thread::sleep(time::Duration::from_micros(1 << fruitless.min(10)));
+    }
 }
 
 pub trait BasicArray<T> {
@@ -112,141 +609,2297 @@ pub trait BasicArray<T> {
     fn len(&self) -> usize;
     fn get(&mut self) -> Option<T>;
     fn put(&mut self, value: T);
+
+    /// The item the next `get` would return, without taking it.
+    fn peek(&self) -> Option<&T>;
+
+    /// Visits the items in an order which, put again, rebuilds the same queue.
+    fn iter(&self) -> Box<dyn Iterator<Item = &T> + '_>;
+
+    /// An empty store configured like this one, which `take_all` swaps in.
+    fn new_empty(&self, maxsize: Option<usize>) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(maxsize)
+    }
+
+    /// Returns a taken item so that it is the next one to be taken again.
+    fn requeue(&mut self, value: T) {
+        self.put(value)
+    }
+
+    /// Whether an item with the same key as `value` is already held, in which case a put
+    /// of `value` succeeds without adding it.
+    fn is_duplicate(&self, _value: &T) -> bool {
+        false
+    }
+
+    /// Whether `value` may be put, besides the bound. A put which is not admitted fails
+    /// with `QueueError::PriorityCap`.
+    fn admits(&self, _value: &T) -> bool {
+        true
+    }
+
+    /// How many items fit without reallocating.
+    fn capacity(&self) -> usize {
+        self.len()
+    }
+
+    /// Releases memory held beyond what the current items need.
+    fn shrink_to_fit(&mut self) {}
+
+    /// Whether at least `n` items are held. Backends whose `len` is expensive can
+    /// override this to stop counting at `n`.
+    fn len_at_least(&self, n: usize) -> bool {
+        self.len() >= n
+    }
+
+    /// Whether at most `n` items are held. Backends whose `len` is expensive can
+    /// override this to stop counting past `n`.
+    fn len_at_most(&self, n: usize) -> bool {
+        self.len() <= n
+    }
+}
+
+/// The state of a queue relevant to its blocked threads, as returned by
+/// `debug_wait_state`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitState {
+    /// Threads blocked waiting for an item.
+    pub waiting_consumers: usize,
+    /// Threads blocked waiting for room to put an item.
+    pub waiting_producers: usize,
+    pub len: usize,
+    pub maxsize: Option<usize>,
+    pub closed: bool,
 }
 
 pub(crate) struct QueueInner<Q, T> {
     _item: PhantomData<T>,
     pub(crate) queue: Mutex<Q>,
-    pub(crate) maxsize: Option<usize>,
+    maxsize: AtomicUsize,
     pub(crate) pending: Mutex<()>,
     pub(crate) not_empty: Condvar,
     pub(crate) not_full: Condvar,
+    pub(crate) drained: Condvar,
+    /// Notified on every put and take, for `LengthWatcher`s.
+    pub(crate) len_changed: Condvar,
+    pub(crate) rate_limit: Option<Mutex<TokenBucket>>,
+    pub(crate) groups: Mutex<Vec<Arc<GroupSignal>>>,
+    /// Notified whenever room may have opened up, as `groups` is for new items.
+    pub(crate) space_groups: Mutex<Vec<Arc<GroupSignal>>>,
+    pub(crate) closed: AtomicBool,
+    pub(crate) shutdown: Option<Arc<AtomicBool>>,
+    pub(crate) producers: AtomicUsize,
+    pub(crate) reserved: AtomicUsize,
+    reserved_slots: AtomicUsize,
+    pub(crate) waiting_consumers: AtomicUsize,
+    pub(crate) waiting_producers: AtomicUsize,
+    producer_line: ProducerLine,
+    max_park: AtomicU64,
+    on_full: Mutex<Option<OnFull>>,
+    writable_wakers: Mutex<Vec<Waker>>,
+    #[cfg(feature = "audit")]
+    pub(crate) audit: AuditRing,
+    #[cfg(feature = "histogram")]
+    pub(crate) histogram: LengthHistogram,
+}
+
+type OnFull = Box<dyn Fn(usize) -> Option<usize> + Send + Sync>;
+
+type Waker = Box<dyn Fn() + Send + Sync>;
+
+/// Counts a thread as blocked for as long as it is alive.
+pub(crate) struct Waiting<'a>(&'a AtomicUsize);
+
+impl<'a> Waiting<'a> {
+    pub(crate) fn new(count: &'a AtomicUsize) -> Self {
+        count.fetch_add(1, Ordering::SeqCst);
+        Self(count)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The producers of a `put_wait` waiting for room, in the order they started waiting.
+struct ProducerLine {
+    tickets: Mutex<VecDeque<u64>>,
+    next: AtomicU64,
+}
+
+impl ProducerLine {
+    fn new() -> Self {
+        Self {
+            tickets: Mutex::new(VecDeque::new()),
+            next: AtomicU64::new(0),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tickets.lock().unwrap().is_empty()
+    }
+}
+
+/// A producer's place in the `ProducerLine`. Only the producer at the head of the line may
+/// put; leaving the line, by putting or by giving up, lets the next one in.
+struct Ticket<'a> {
+    line: &'a ProducerLine,
+    pending: &'a Mutex<()>,
+    not_full: &'a Condvar,
+    id: u64,
+}
+
+impl<'a> Ticket<'a> {
+    fn new<Q, T>(inner: &'a QueueInner<Q, T>) -> Self {
+        let line = &inner.producer_line;
+        let id = line.next.fetch_add(1, Ordering::SeqCst);
+        line.tickets.lock().unwrap().push_back(id);
+        Self {
+            line,
+            pending: &inner.pending,
+            not_full: &inner.not_full,
+            id,
+        }
+    }
+
+    fn is_first(&self) -> bool {
+        self.line.tickets.lock().unwrap().front() == Some(&self.id)
+    }
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        let mut tickets = self.line.tickets.lock().unwrap();
+        let was_first = tickets.front() == Some(&self.id);
+        tickets.retain(|id| *id != self.id);
+        drop(tickets);
+        if was_first {
+            // Whoever is next may have been woken before it was at the head, and gone
+            // back to sleep.
+            let _pending = self.pending.lock().unwrap();
+            self.not_full.notify_all();
+        }
+    }
 }
 
 impl<Q: BasicArray<T>, T> QueueInner<Q, T> {
     pub fn new(maxsize: Option<usize>) -> Self {
+        Self::with_array(Q::new(maxsize), maxsize)
+    }
+
+    pub(crate) fn with_array(queue: Q, maxsize: Option<usize>) -> Self {
         Self {
             _item: PhantomData,
-            queue: Mutex::new(Q::new(maxsize)),
-            maxsize,
+            queue: Mutex::new(queue),
+            maxsize: AtomicUsize::new(maxsize.unwrap_or(usize::MAX)),
             pending: Mutex::new(()),
             not_empty: Condvar::new(),
             not_full: Condvar::new(),
+            drained: Condvar::new(),
+            len_changed: Condvar::new(),
+            rate_limit: None,
+            groups: Mutex::new(Vec::new()),
+            space_groups: Mutex::new(Vec::new()),
+            closed: AtomicBool::new(false),
+            shutdown: None,
+            producers: AtomicUsize::new(0),
+            reserved: AtomicUsize::new(0),
+            reserved_slots: AtomicUsize::new(0),
+            waiting_consumers: AtomicUsize::new(0),
+            waiting_producers: AtomicUsize::new(0),
+            producer_line: ProducerLine::new(),
+            max_park: AtomicU64::new(0),
+            on_full: Mutex::new(None),
+            writable_wakers: Mutex::new(Vec::new()),
+            #[cfg(feature = "audit")]
+            audit: AuditRing::new(),
+            #[cfg(feature = "histogram")]
+            histogram: LengthHistogram::new(),
         }
     }
-}
-
-pub struct BaseQueue<Q, T> {
-    pub(crate) inner: Arc<QueueInner<Q, T>>,
-}
 
-impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
-    pub fn new(maxsize: Option<usize>) -> Self {
-        Self {
-            inner: Arc::new(QueueInner::new(maxsize)),
+    /// The current bound. Only changed while holding the `queue` lock, so it is consistent
+    /// with the length seen under that lock.
+    pub(crate) fn maxsize(&self) -> Option<usize> {
+        match self.maxsize.load(Ordering::SeqCst) {
+            usize::MAX => None,
+            maxsize => Some(maxsize),
         }
     }
-}
 
-impl<Q: BasicArray<T>, T> Queue<T> for BaseQueue<Q, T> {
-    fn len(&self) -> usize {
-        self.inner.queue.lock().unwrap().len()
+    pub(crate) fn set_maxsize(&self, maxsize: Option<usize>) {
+        self.maxsize
+            .store(maxsize.unwrap_or(usize::MAX), Ordering::SeqCst);
     }
 
-    fn is_empty(&self) -> bool {
-        self.len() == 0
+    /// Whether a queue holding `len` items is full, counting slots reserved by permits and
+    /// the slots kept for `put_reserved`.
+    pub(crate) fn is_full_at(&self, len: usize) -> bool {
+        self.is_hard_full_at(len + self.reserved_slots.load(Ordering::SeqCst))
     }
 
-    fn is_full(&self) -> bool {
-        Some(self.len()) == self.inner.maxsize
+    /// Whether a queue holding `len` items is full even for `put_reserved`.
+    pub(crate) fn is_hard_full_at(&self, len: usize) -> bool {
+        let reserved = self.reserved.load(Ordering::SeqCst);
+        matches!(self.maxsize(), Some(maxsize) if len + reserved >= maxsize)
     }
 
-    fn get(&mut self) -> Result<T, QueueError> {
-        if let Some(value) = self.inner.queue.lock().unwrap().get() {
-            self.inner.not_full.notify_one();
-            Ok(value)
-        } else {
-            Err(QueueError::Empty)
+    pub(crate) fn set_max_park(&self, max_park: time::Duration) {
+        let nanos = u64::try_from(max_park.as_nanos()).unwrap_or(u64::MAX);
+        self.max_park.store(nanos, Ordering::SeqCst);
+    }
+
+    pub(crate) fn max_park(&self) -> Option<time::Duration> {
+        match self.max_park.load(Ordering::SeqCst) {
+            0 => None,
+            nanos => Some(time::Duration::from_nanos(nanos)),
         }
     }
 
-    fn get_wait(&mut self, timeout: time::Duration) -> Result<T, QueueError> {
-        if timeout.is_zero() {
-            while self.is_empty() {
-                let _pending = self
-                    .inner
-                    .not_empty
-                    .wait(self.inner.pending.lock().unwrap())
-                    .unwrap();
+    /// Waits on `condvar` until `pred` holds, for at most `timeout`, or without limit if
+    /// it is zero, and returns whether `pred` held in time. `pred` is checked under
+    /// `pending`, which notifiers take after changing the state, so no notify is missed.
+    /// Gives up at once if the shutdown flag is set.
+    pub(crate) fn wait_until(
+        &self,
+        condvar: &Condvar,
+        pred: impl Fn() -> bool,
+        timeout: time::Duration,
+    ) -> bool {
+        let deadline = (!timeout.is_zero()).then(|| time::Instant::now() + timeout);
+        let mut pending = self.pending.lock().unwrap();
+        loop {
+            if pred() {
+                return true;
             }
-        } else {
-            let timestamp = time::SystemTime::now();
-            let mut remaining = timeout;
-            while self.is_empty() {
-                let ret = self
-                    .inner
-                    .not_empty
-                    .wait_timeout(self.inner.pending.lock().unwrap(), remaining)
-                    .unwrap();
-                if ret.1.timed_out() {
-                    return Err(QueueError::Empty);
-                }
-                let elapsed = timestamp.elapsed().unwrap();
-                if elapsed >= timeout {
-                    return Err(QueueError::Empty);
-                }
-                remaining = timeout - elapsed;
+            if self.is_shut_down() {
+                return false;
             }
+            let remaining = match deadline {
+                None => None,
+                Some(deadline) => match deadline.checked_duration_since(time::Instant::now()) {
+                    Some(remaining) if !remaining.is_zero() => Some(remaining),
+                    _ => return false,
+                },
+            };
+            pending = self.wait_chunk(condvar, pending, remaining);
         }
-        self.get()
     }
 
-    fn put(&mut self, value: T) -> Result<(), PutError<T>> {
-        let mut queue = self.inner.queue.lock().unwrap();
-        if Some(queue.len()) == self.inner.maxsize {
-            return Err(PutError(value, QueueError::Full));
+    /// Waits on `condvar` for at most `remaining`, or without limit if it is `None`, but
+    /// never longer than `max_park` at a time, so that predicates are re-checked at least
+    /// that often even without a notify.
+    fn wait_chunk<'a>(
+        &self,
+        condvar: &Condvar,
+        pending: MutexGuard<'a, ()>,
+        remaining: Option<time::Duration>,
+    ) -> MutexGuard<'a, ()> {
+        match (remaining, self.max_park()) {
+            (None, None) => condvar.wait(pending).unwrap(),
+            (Some(duration), None) | (None, Some(duration)) => {
+                condvar.wait_timeout(pending, duration).unwrap().0
+            }
+            (Some(remaining), Some(max_park)) => {
+                condvar
+                    .wait_timeout(pending, remaining.min(max_park))
+                    .unwrap()
+                    .0
+            }
         }
-        queue.put(value);
-        self.inner.not_empty.notify_one();
-        Ok(())
     }
 
-    fn put_wait(&mut self, value: T, timeout: time::Duration) -> Result<(), PutError<T>> {
-        if timeout.is_zero() {
-            while self.is_full() {
-                let _pending = self
-                    .inner
-                    .not_full
-                    .wait(self.inner.pending.lock().unwrap())
-                    .unwrap();
-            }
-        } else {
-            let timestamp = time::SystemTime::now();
-            let mut remaining = timeout;
-            while self.is_full() {
-                let ret = self
-                    .inner
-                    .not_full
-                    .wait_timeout(self.inner.pending.lock().unwrap(), remaining)
-                    .unwrap();
-                if ret.1.timed_out() {
-                    return Err(PutError(value, QueueError::Full));
-                }
-                let elapsed = timestamp.elapsed().unwrap();
-                if elapsed >= timeout {
-                    return Err(PutError(value, QueueError::Full));
+    /// Checks, in debug builds, that a queue holding `len` items is within its bound.
+    /// Every put checks fullness under the same lock, so only a bug can trip this.
+    pub(crate) fn debug_assert_within_bound(&self, len: usize) {
+        debug_assert!(
+            !matches!(self.maxsize(), Some(maxsize) if len > maxsize),
+            "queue holds {} items, over its bound of {:?}",
+            len,
+            self.maxsize()
+        );
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Samples `len` into the length histogram with the `histogram` feature, and does
+    /// nothing without it. Called with the queue lock still held after each change to the
+    /// length, so that the sample is the length the change left.
+    #[cfg_attr(not(feature = "histogram"), allow(unused_variables))]
+    pub(crate) fn record_len(&self, len: usize) {
+        #[cfg(feature = "histogram")]
+        self.histogram.record(len);
+    }
+
+    /// Whether the shutdown flag given to `with_shutdown` is set.
+    pub(crate) fn is_shut_down(&self) -> bool {
+        matches!(&self.shutdown, Some(flag) if flag.load(Ordering::SeqCst))
+    }
+
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        {
+            let _pending = self.pending.lock().unwrap();
+            self.not_empty.notify_all();
+            self.not_full.notify_all();
+            self.len_changed.notify_all();
+        }
+        self.notify_groups(usize::MAX);
+        self.notify_space_groups(usize::MAX);
+        self.wake_writable();
+    }
+
+    pub(crate) fn notify_not_empty(&self) {
+        {
+            let _pending = self.pending.lock().unwrap();
+            self.not_empty.notify_one();
+            self.len_changed.notify_all();
+        }
+        self.notify_groups(1);
+    }
+
+    /// Wakes as many blocked consumers as `count` new items can serve: all of them with a
+    /// single notify if there are no more than `count`, or else one notify per item, so
+    /// that the rest stay asleep.
+    pub(crate) fn notify_not_empty_count(&self, count: usize) {
+        {
+            let _pending = self.pending.lock().unwrap();
+            if count >= self.waiting_consumers.load(Ordering::SeqCst) {
+                self.not_empty.notify_all();
+            } else {
+                for _ in 0..count {
+                    self.not_empty.notify_one();
                 }
-                remaining = timeout - elapsed;
             }
+            self.len_changed.notify_all();
+        }
+        self.notify_groups(count);
+    }
+
+    pub(crate) fn notify_not_full(&self) {
+        {
+            let _pending = self.pending.lock().unwrap();
+            // With several producers waiting, only the one at the head of the line can use
+            // the room, and a single notify might miss it.
+            match self.waiting_producers.load(Ordering::SeqCst) > 1 {
+                true => self.not_full.notify_all(),
+                false => self.not_full.notify_one(),
+            }
+            self.drained.notify_all();
+            self.len_changed.notify_all();
+        }
+        self.notify_space_groups(1);
+        self.wake_writable();
+    }
+
+    pub(crate) fn notify_all_not_full(&self) {
+        {
+            let _pending = self.pending.lock().unwrap();
+            self.not_full.notify_all();
+            self.drained.notify_all();
+            self.len_changed.notify_all();
+        }
+        self.notify_space_groups(usize::MAX);
+        self.wake_writable();
+    }
+
+    /// Wakes the waiters on the length of the queue, for removals which don't make room,
+    /// like evictions by a shrinking bound.
+    pub(crate) fn notify_len_changed(&self) {
+        let _pending = self.pending.lock().unwrap();
+        self.drained.notify_all();
+        self.len_changed.notify_all();
+    }
+
+    /// Calls and forgets the wakers registered with `register_writable_waker`. They are
+    /// called without any lock held, so they may use the queue.
+    fn wake_writable(&self) {
+        let wakers = std::mem::take(&mut *self.writable_wakers.lock().unwrap());
+        for waker in wakers {
+            waker();
+        }
+    }
+
+    /// Notifies the groups of `count` new items.
+    pub(crate) fn notify_groups(&self, count: usize) {
+        for group in self.groups.lock().unwrap().iter() {
+            group.notify(count);
+        }
+    }
+
+    /// Notifies the groups waiting for room of `count` freed slots.
+    fn notify_space_groups(&self, count: usize) {
+        for group in self.space_groups.lock().unwrap().iter() {
+            group.notify(count);
         }
-        self.put(value)
     }
 }
 
-impl<Q, T> Clone for BaseQueue<Q, T> {
-    fn clone(&self) -> Self {
+pub struct BaseQueue<Q, T> {
+    pub(crate) inner: Arc<QueueInner<Q, T>>,
+}
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    pub fn new(maxsize: Option<usize>) -> Self {
         Self {
-            inner: Arc::clone(&self.inner),
+            inner: Arc::new(QueueInner::new(maxsize)),
         }
     }
+
+    /// Like `get_wait`, but calls `on_wake` with the remaining time whenever a wakeup finds
+    /// no item, e.g. a spurious one or one whose item another consumer took first. The
+    /// remaining time of a wait without limit is `Duration::MAX`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     let mut wakeups = Vec::new();
+    ///     let ret = consumer.get_wait_observed(time::Duration::from_millis(2000), |remaining| {
+    ///         wakeups.push(remaining)
+    ///     });
+    ///     (ret, wakeups)
+    /// });
+    ///
+    /// thread::sleep(time::Duration::from_millis(50));
+    /// for i in 0..3 {
+    ///     queue.put(i).unwrap();
+    ///     let _ = queue.get();
+    ///     thread::sleep(time::Duration::from_millis(20));
+    /// }
+    /// queue.put(3).unwrap();
+    ///
+    /// // Each item taken from under the consumer woke it for nothing.
+    /// let (ret, wakeups) = th.join().unwrap();
+    /// assert!(wakeups.len() >= ret.unwrap());
+    /// assert!(wakeups.iter().all(|remaining| *remaining < time::Duration::from_millis(2000)));
+    /// ```
+    pub fn get_wait_observed(
+        &self,
+        timeout: time::Duration,
+        mut on_wake: impl FnMut(time::Duration),
+    ) -> Result<T, QueueError> {
+        let timestamp = time::Instant::now();
+        let mut fruitless = 0;
+        let mut woken = false;
+        loop {
+            if self.inner.is_shut_down() {
+                return Err(QueueError::ShutDown);
+            }
+            match self.get() {
+                Err(QueueError::Empty) => {}
+                ret => return ret,
+            }
+            let elapsed = timestamp.elapsed();
+            if !timeout.is_zero() && elapsed >= timeout {
+                return Err(QueueError::Empty);
+            }
+            let remaining = match timeout.is_zero() {
+                true => time::Duration::MAX,
+                false => timeout - elapsed,
+            };
+            if woken {
+                fruitless += 1;
+                on_wake(remaining);
+                if timeout.is_zero() {
+                    backoff(fruitless);
+                }
+            }
+            if !timeout.is_zero() && timeout < SHORT_WAIT {
+                thread::yield_now();
+                continue;
+            }
+            // Return on the first wakeup, whether or not it finds an item, so that `on_wake`
+            // sees every fruitless one.
+            let waited = Cell::new(false);
+            let _waiting = Waiting::new(&self.inner.waiting_consumers);
+            self.inner.wait_until(
+                &self.inner.not_empty,
+                || waited.replace(true) || !self.is_empty() || self.inner.is_closed(),
+                match timeout.is_zero() {
+                    true => time::Duration::ZERO,
+                    false => remaining,
+                },
+            );
+            woken = true;
+        }
+    }
+
+    /// Waits like `get_wait` for the next item, calls `f` on it in place, then removes it
+    /// and returns what `f` returned, for callers which only need something computed from
+    /// the item. The queue stays locked while `f` runs.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue, QueueError};
+    ///
+    /// #[derive(Debug)]
+    /// struct Job {
+    ///     id: u32,
+    ///     payload: Vec<u8>,
+    /// }
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// queue.put(Job { id: 7, payload: vec![0; 1024] }).unwrap();
+    ///
+    /// let id = queue.with_next(time::Duration::from_millis(100), |job| job.id).unwrap();
+    /// assert_eq!(id, 7);
+    /// assert!(queue.is_empty());
+    ///
+    /// let ret = queue.with_next(time::Duration::from_millis(10), |job| job.payload.len());
+    /// assert_eq!(ret.unwrap_err(), QueueError::Empty);
+    /// ```
+    pub fn with_next<R>(
+        &self,
+        timeout: time::Duration,
+        f: impl FnOnce(&T) -> R,
+    ) -> Result<R, QueueError> {
+        let timestamp = time::Instant::now();
+        loop {
+            if self.inner.is_shut_down() {
+                return Err(QueueError::ShutDown);
+            }
+            let mut queue = self.inner.queue.lock().unwrap();
+            if let Some(next) = queue.peek() {
+                let ret = f(next);
+                let value = queue.get();
+                self.inner.record_len(queue.len());
+                drop(queue);
+                drop(value);
+                self.inner.notify_not_full();
+                return Ok(ret);
+            }
+            drop(queue);
+            if self.inner.is_closed() {
+                return Err(QueueError::Closed);
+            }
+            let remaining = match timeout.is_zero() {
+                true => time::Duration::ZERO,
+                false => match timeout.checked_sub(timestamp.elapsed()) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    _ => return Err(QueueError::Empty),
+                },
+            };
+            let _waiting = Waiting::new(&self.inner.waiting_consumers);
+            self.inner.wait_until(
+                &self.inner.not_empty,
+                || !self.is_empty() || self.inner.is_closed(),
+                remaining,
+            );
+        }
+    }
+
+    /// Like `get_wait`, but returns `f()` instead of an error when no item could be taken,
+    /// whether the wait timed out or the queue was closed.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// assert_eq!(queue.get_wait_or_else(time::Duration::from_millis(10), || -1), -1);
+    ///
+    /// let producer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     thread::sleep(time::Duration::from_millis(10));
+    ///     producer.put(1).unwrap();
+    /// });
+    /// assert_eq!(queue.get_wait_or_else(time::Duration::from_millis(1000), || -1), 1);
+    /// th.join().unwrap();
+    /// ```
+    pub fn get_wait_or_else(&self, timeout: time::Duration, f: impl FnOnce() -> T) -> T {
+        self.get_wait(timeout).unwrap_or_else(|_| f())
+    }
+
+    /// Caps how long a blocked `get_wait`, `put_wait`, `get_batch_wait` or `flush` sleeps
+    /// before re-checking its condition, so that state changes which don't notify the
+    /// queue, like an external cancel flag seen by a `get_wait_observed` callback, are
+    /// noticed within `max_park`. A zero `max_park` removes the cap.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::FifoQueue;
+    ///
+    /// let queue: FifoQueue<i32> = FifoQueue::new(None);
+    /// queue.set_max_park(time::Duration::from_millis(20));
+    ///
+    /// let cancel = Arc::new(AtomicBool::new(false));
+    /// let consumer = queue.clone();
+    /// let flag = Arc::clone(&cancel);
+    /// let th = thread::spawn(move || {
+    ///     let mut observed = None;
+    ///     let _ = consumer.get_wait_observed(time::Duration::from_millis(300), |_| {
+    ///         if observed.is_none() && flag.load(Ordering::SeqCst) {
+    ///             observed = Some(time::Instant::now());
+    ///         }
+    ///     });
+    ///     observed.unwrap()
+    /// });
+    ///
+    /// thread::sleep(time::Duration::from_millis(50));
+    /// let cancelled_at = time::Instant::now();
+    /// cancel.store(true, Ordering::SeqCst);
+    /// let observed_at = th.join().unwrap();
+    /// assert!(observed_at.duration_since(cancelled_at) < time::Duration::from_millis(20 + 30));
+    /// ```
+    pub fn set_max_park(&self, max_park: time::Duration) {
+        self.inner.set_max_park(max_park);
+    }
+
+    /// Returns the number of threads blocked waiting for an item.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// assert_eq!(queue.waiting_consumers(), 0);
+    ///
+    /// let consumers: Vec<_> = (0..3)
+    ///     .map(|_| {
+    ///         let consumer = queue.clone();
+    ///         thread::spawn(move || consumer.get_wait(time::Duration::from_millis(5000)))
+    ///     })
+    ///     .collect();
+    /// while queue.waiting_consumers() < 3 {
+    ///     thread::sleep(time::Duration::from_millis(1));
+    /// }
+    /// assert_eq!(queue.waiting_consumers(), 3);
+    ///
+    /// for i in 0..3 {
+    ///     queue.put(i).unwrap();
+    /// }
+    /// for consumer in consumers {
+    ///     consumer.join().unwrap().unwrap();
+    /// }
+    /// assert_eq!(queue.waiting_consumers(), 0);
+    /// ```
+    pub fn waiting_consumers(&self) -> usize {
+        self.inner.waiting_consumers.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of threads blocked waiting for room to put an item.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(1));
+    /// queue.put(0).unwrap();
+    ///
+    /// let producers: Vec<_> = (1..3)
+    ///     .map(|i| {
+    ///         let producer = queue.clone();
+    ///         thread::spawn(move || producer.put_wait(i, time::Duration::from_millis(5000)))
+    ///     })
+    ///     .collect();
+    /// while queue.waiting_producers() < 2 {
+    ///     thread::sleep(time::Duration::from_millis(1));
+    /// }
+    /// assert_eq!(queue.waiting_producers(), 2);
+    ///
+    /// for _ in 0..3 {
+    ///     queue.get_wait(time::Duration::from_millis(5000)).unwrap();
+    /// }
+    /// for producer in producers {
+    ///     producer.join().unwrap().unwrap();
+    /// }
+    /// assert_eq!(queue.waiting_producers(), 0);
+    /// ```
+    pub fn waiting_producers(&self) -> usize {
+        self.inner.waiting_producers.load(Ordering::SeqCst)
+    }
+
+    /// Returns a snapshot of why threads may be blocked on the queue, for diagnosing
+    /// stuck producers or consumers. The fields are read one after another, so they may
+    /// be slightly inconsistent while the queue is in use.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue, WaitState};
+    ///
+    /// let queue: FifoQueue<i32> = FifoQueue::new(Some(4));
+    /// let consumers: Vec<_> = (0..2)
+    ///     .map(|_| {
+    ///         let consumer = queue.clone();
+    ///         thread::spawn(move || consumer.get_wait(time::Duration::ZERO))
+    ///     })
+    ///     .collect();
+    /// while queue.waiting_consumers() < 2 {
+    ///     thread::sleep(time::Duration::from_millis(1));
+    /// }
+    ///
+    /// assert_eq!(
+    ///     queue.debug_wait_state(),
+    ///     WaitState {
+    ///         waiting_consumers: 2,
+    ///         waiting_producers: 0,
+    ///         len: 0,
+    ///         maxsize: Some(4),
+    ///         closed: false,
+    ///     }
+    /// );
+    ///
+    /// queue.close();
+    /// for consumer in consumers {
+    ///     assert!(consumer.join().unwrap().is_err());
+    /// }
+    /// let state = queue.debug_wait_state();
+    /// assert_eq!(state.waiting_consumers, 0);
+    /// assert!(state.closed);
+    /// ```
+    pub fn debug_wait_state(&self) -> WaitState {
+        WaitState {
+            waiting_consumers: self.waiting_consumers(),
+            waiting_producers: self.waiting_producers(),
+            len: self.len(),
+            maxsize: self.inner.maxsize(),
+            closed: self.inner.is_closed(),
+        }
+    }
+
+    /// Puts `value` and returns the length of the queue after it was put, or `None` if it
+    /// was dropped as a duplicate.
+    pub(crate) fn insert(&self, value: T) -> Result<Option<usize>, PutError<T>> {
+        self.insert_until(value, None, false)
+    }
+
+    /// Like `insert`, but gives up with `QueueError::Full` if the queue lock can't be
+    /// taken before `deadline`. A contended lock is retried by yielding a few times, then
+    /// with sleeps growing up to `LOCK_RETRY_MAX`, so that waiting on it doesn't keep a
+    /// core busy. A `privileged` put may use the
+    /// slots kept for `put_reserved`.
+    fn insert_until(
+        &self,
+        value: T,
+        deadline: Option<time::Instant>,
+        privileged: bool,
+    ) -> Result<Option<usize>, PutError<T>> {
+        if self.inner.is_closed() {
+            audit_event!(self.inner, AuditOp::Reject(QueueError::Closed), self.len());
+            return Err(PutError(value, QueueError::Closed));
+        }
+        let mut queue = match deadline {
+            None => self.inner.queue.lock().unwrap(),
+            Some(deadline) => {
+                let mut spins = 0;
+                loop {
+                    match self.inner.queue.try_lock() {
+                        Ok(queue) => break queue,
+                        Err(TryLockError::WouldBlock) if time::Instant::now() >= deadline => {
+                            return Err(PutError(value, QueueError::Full));
+                        }
+                        Err(TryLockError::WouldBlock) if spins < LOCK_SPINS => {
+                            spins += 1;
+                            thread::yield_now();
+                        }
+                        Err(TryLockError::WouldBlock) => {
+                            let delay =
+                                time::Duration::from_micros(1 << (spins - LOCK_SPINS).min(10));
+                            let remaining =
+                                deadline.saturating_duration_since(time::Instant::now());
+                            thread::sleep(delay.min(LOCK_RETRY_MAX).min(remaining));
+                            spins = spins.saturating_add(1);
+                        }
+                        Err(TryLockError::Poisoned(err)) => panic!("{}", err),
+                    }
+                }
+            }
+        };
+        if queue.is_duplicate(&value) {
+            return Ok(None);
+        }
+        // The bound only changes under the queue lock, so it can't move between this check
+        // and the put below.
+        let full = match privileged {
+            true => self.inner.is_hard_full_at(queue.len()),
+            false => self.inner.is_full_at(queue.len()),
+        };
+        if full && !self.grow_on_full(queue.len()) {
+            trace_event!(len = queue.len(), "full");
+            audit_event!(self.inner, AuditOp::Reject(QueueError::Full), queue.len());
+            return Err(PutError(value, QueueError::Full));
+        }
+        if !queue.admits(&value) {
+            audit_event!(
+                self.inner,
+                AuditOp::Reject(QueueError::PriorityCap),
+                queue.len()
+            );
+            return Err(PutError(value, QueueError::PriorityCap));
+        }
+        if let Some(bucket) = &self.inner.rate_limit {
+            if !bucket.lock().unwrap().try_acquire() {
+                audit_event!(
+                    self.inner,
+                    AuditOp::Reject(QueueError::RateLimited),
+                    queue.len()
+                );
+                return Err(PutError(value, QueueError::RateLimited));
+            }
+        }
+        queue.put(value);
+        let len = queue.len();
+        self.inner.debug_assert_within_bound(len);
+        audit_event!(self.inner, AuditOp::Put, len);
+        self.inner.record_len(len);
+        drop(queue);
+        trace_event!(len, "put");
+        self.inner.notify_not_empty();
+        Ok(Some(len))
+    }
+
+    /// Puts `value` if that can be done without waiting, for event loops which must never
+    /// block. Unlike `put`, a put which can't be done right now for any reason but the
+    /// queue being closed is reported as `TryPutError::Full`, meaning retry later; a
+    /// waker registered with `register_writable_waker` tells when.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue, TryPutError};
+    ///
+    /// let queue = FifoQueue::new(Some(1));
+    /// assert_eq!(queue.put_nb(1), Ok(()));
+    /// assert_eq!(queue.put_nb(2), Err(TryPutError::Full(2)));
+    ///
+    /// queue.close();
+    /// assert_eq!(queue.put_nb(3), Err(TryPutError::Closed(3)));
+    /// ```
+    pub fn put_nb(&self, value: T) -> Result<(), TryPutError<T>> {
+        // Don't wait for the queue lock either, as a busy queue is as good as a full one.
+        match self.insert_until(value, Some(time::Instant::now()), false) {
+            Ok(_) => Ok(()),
+            Err(PutError(value, QueueError::Closed)) => Err(TryPutError::Closed(value)),
+            Err(PutError(value, _)) => Err(TryPutError::Full(value)),
+        }
+    }
+
+    /// Registers `waker` to be called once, the next time room is freed in the queue or
+    /// the queue is closed, so an event loop knows when to retry `put_nb`. Register again
+    /// after each wakeup to keep being notified, and retry `put_nb` once after registering,
+    /// in case room was freed in between. `waker` may be called from any thread which
+    /// takes items.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use rueue::{FifoQueue, Queue, TryPutError};
+    ///
+    /// let queue = FifoQueue::new(Some(1));
+    /// queue.put_nb(1).unwrap();
+    /// assert!(matches!(queue.put_nb(2), Err(TryPutError::Full(2))));
+    ///
+    /// let woken = Arc::new(AtomicUsize::new(0));
+    /// let counter = Arc::clone(&woken);
+    /// queue.register_writable_waker(move || {
+    ///     counter.fetch_add(1, Ordering::SeqCst);
+    /// });
+    /// assert_eq!(woken.load(Ordering::SeqCst), 0);
+    ///
+    /// queue.get().unwrap();
+    /// assert_eq!(woken.load(Ordering::SeqCst), 1);
+    /// queue.put_nb(2).unwrap();
+    ///
+    /// // The waker was used up by the first wakeup.
+    /// queue.get().unwrap();
+    /// assert_eq!(woken.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn register_writable_waker(&self, waker: impl Fn() + Send + Sync + 'static) {
+        self.inner
+            .writable_wakers
+            .lock()
+            .unwrap()
+            .push(Box::new(waker));
+    }
+
+    /// Keeps the last `slots` slots of a bounded queue for `put_reserved`, so that
+    /// privileged producers still find room when the queue is full for everyone else.
+    /// Other puts, and `is_full`, treat the queue as full once only the kept slots are left.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(Some(4));
+    /// queue.set_reserved_slots(2);
+    ///
+    /// queue.put(0).unwrap();
+    /// queue.put(1).unwrap();
+    /// assert!(queue.is_full());
+    /// assert_eq!(queue.put(2), Err(PutError(2, QueueError::Full)));
+    ///
+    /// queue.put_reserved(2).unwrap();
+    /// queue.put_reserved(3).unwrap();
+    /// assert_eq!(queue.put_reserved(4), Err(PutError(4, QueueError::Full)));
+    /// assert_eq!(queue.len(), 4);
+    /// ```
+    pub fn set_reserved_slots(&self, slots: usize) {
+        let queue = self.inner.queue.lock().unwrap();
+        self.inner.reserved_slots.store(slots, Ordering::SeqCst);
+        drop(queue);
+        self.inner.notify_all_not_full();
+    }
+
+    /// Puts `value` like `put`, but may use the slots kept with `set_reserved_slots`.
+    pub fn put_reserved(&self, value: T) -> Result<(), PutError<T>> {
+        self.insert_until(value, None, true).map(|_| ())
+    }
+
+    /// Like `put_wait`, but retries `put` with sleeps doubling from `base` up to `max`
+    /// instead of waiting on the queue's condition variable. Producers which can tolerate
+    /// extra latency use this to stay off the wakeup path of more urgent ones. A zero
+    /// `timeout` retries without limit.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(Some(1));
+    /// queue.put(0).unwrap();
+    ///
+    /// let base = time::Duration::from_millis(1);
+    /// let max = time::Duration::from_millis(8);
+    /// assert!(matches!(
+    ///     queue.put_poll(1, time::Duration::from_millis(20), base, max),
+    ///     Err(PutError(1, QueueError::Full))
+    /// ));
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     thread::sleep(time::Duration::from_millis(30));
+    ///     consumer.get().unwrap()
+    /// });
+    /// let timestamp = time::Instant::now();
+    /// queue.put_poll(1, time::Duration::from_millis(1000), base, max).unwrap();
+    /// assert!(timestamp.elapsed() < time::Duration::from_millis(1000));
+    /// assert_eq!(th.join().unwrap(), 0);
+    /// assert_eq!(queue.get().unwrap(), 1);
+    /// ```
+    pub fn put_poll(
+        &self,
+        mut value: T,
+        timeout: time::Duration,
+        base: time::Duration,
+        max: time::Duration,
+    ) -> Result<(), PutError<T>> {
+        let timestamp = time::Instant::now();
+        let mut delay = base.min(max);
+        loop {
+            match self.insert(value) {
+                Err(PutError(rejected, err @ (QueueError::Full | QueueError::RateLimited))) => {
+                    let elapsed = timestamp.elapsed();
+                    if !timeout.is_zero() && elapsed >= timeout {
+                        return Err(PutError(rejected, err));
+                    }
+                    value = rejected;
+                }
+                ret => return ret.map(|_| ()),
+            }
+            if timeout.is_zero() {
+                thread::sleep(delay);
+            } else {
+                thread::sleep(delay.min(timeout.saturating_sub(timestamp.elapsed())));
+            }
+            delay = (delay * 2).min(max);
+        }
+    }
+
+    /// Asks the `on_full` hook for a larger bound, and applies it if that makes room for
+    /// one more item. Called with the queue lock held.
+    pub(crate) fn grow_on_full(&self, len: usize) -> bool {
+        let Some(maxsize) = self.inner.maxsize() else {
+            return false;
+        };
+        let on_full = self.inner.on_full.lock().unwrap();
+        match on_full.as_ref().and_then(|f| f(maxsize)) {
+            Some(new_maxsize) if new_maxsize > maxsize => {
+                self.inner.set_maxsize(Some(new_maxsize));
+                !self.inner.is_full_at(len)
+            }
+            _ => false,
+        }
+    }
+
+    /// Sets a hook called with the current bound whenever a put finds the queue full.
+    /// Returning `Some` larger bound raises the bound and lets the put proceed, while
+    /// `None` rejects the put as usual. The hook runs with the queue locked, so it must
+    /// not use the queue itself.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(Some(2));
+    /// queue.set_on_full(|maxsize| (maxsize < 4).then_some(maxsize * 2));
+    ///
+    /// for i in 0..4 {
+    ///     queue.put(i).unwrap();
+    /// }
+    /// assert_eq!(queue.maxsize(), Some(4));
+    /// assert_eq!(queue.put(4), Err(PutError(4, QueueError::Full)));
+    /// assert_eq!(queue.len(), 4);
+    /// ```
+    pub fn set_on_full(&self, f: impl Fn(usize) -> Option<usize> + Send + Sync + 'static) {
+        *self.inner.on_full.lock().unwrap() = Some(Box::new(f));
+    }
+
+    /// Puts `value` like `put`, and reports whether the queue is now above
+    /// `high_watermark`, so producers can slow down before it is full.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, PutOutcome, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(4));
+    ///
+    /// assert_eq!(queue.put_watermarked(1, 2).unwrap(), PutOutcome::Ok);
+    /// assert_eq!(queue.put_watermarked(2, 2).unwrap(), PutOutcome::Ok);
+    /// assert_eq!(queue.put_watermarked(3, 2).unwrap(), PutOutcome::OkNearFull);
+    /// assert_eq!(queue.put_watermarked(4, 2).unwrap(), PutOutcome::OkNearFull);
+    /// assert!(queue.put_watermarked(5, 2).is_err());
+    /// ```
+    pub fn put_watermarked(
+        &self,
+        value: T,
+        high_watermark: usize,
+    ) -> Result<PutOutcome, PutError<T>> {
+        match self.insert(value)? {
+            None => Ok(PutOutcome::Duplicate),
+            Some(len) if len > high_watermark => Ok(PutOutcome::OkNearFull),
+            Some(_) => Ok(PutOutcome::Ok),
+        }
+    }
+
+    /// Lowers the bound to `maxsize`, removing items in dequeue order until the queue fits
+    /// and returning them. Slots reserved by permits count toward the bound, so items are
+    /// evicted to make room for them too.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(5));
+    /// for i in 0..5 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// // Waiters on the length see evictions like any other removal.
+    /// let mut watcher = queue.watch();
+    /// let th = thread::spawn(move || watcher.changed(time::Duration::from_millis(5000)));
+    /// thread::sleep(time::Duration::from_millis(50));
+    ///
+    /// let timestamp = time::Instant::now();
+    /// let evicted = queue.set_maxsize_evict(2);
+    /// assert_eq!(th.join().unwrap(), Some(2));
+    /// assert!(timestamp.elapsed() < time::Duration::from_millis(2500));
+    /// assert_eq!(evicted, vec![0, 1, 2]);
+    /// assert_eq!(queue.len(), 2);
+    /// assert_eq!(queue.is_full(), true);
+    /// assert!(queue.put(5).is_err());
+    /// ```
+    pub fn set_maxsize_evict(&self, maxsize: usize) -> Vec<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        self.inner.set_maxsize(Some(maxsize));
+        let reserved = self.inner.reserved.load(Ordering::SeqCst);
+        let mut evicted = Vec::with_capacity((queue.len() + reserved).saturating_sub(maxsize));
+        while queue.len() + reserved > maxsize {
+            match queue.get() {
+                Some(value) => evicted.push(value),
+                None => break,
+            }
+        }
+        if !evicted.is_empty() {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        if !evicted.is_empty() {
+            self.inner.notify_len_changed();
+        }
+        evicted
+    }
+
+    /// Closes the queue. Further puts fail with `QueueError::Closed`, and once the
+    /// remaining items are taken, `get` and `get_wait` return `QueueError::Closed`.
+    /// Threads blocked in `get_wait` or `put_wait` are woken.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// queue.put(1).unwrap();
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     assert_eq!(consumer.get_wait(time::Duration::from_millis(1000)).unwrap(), 1);
+    ///     consumer.get_wait(time::Duration::from_millis(1000))
+    /// });
+    /// thread::sleep(time::Duration::from_millis(10));
+    /// queue.close();
+    ///
+    /// assert!(matches!(th.join().unwrap(), Err(QueueError::Closed)));
+    /// assert!(matches!(queue.put(2), Err(PutError(2, QueueError::Closed))));
+    /// ```
+    pub fn close(&self) {
+        self.inner.close();
+    }
+
+    /// Whether the queue has been closed, either with `close` or by dropping its last
+    /// `Producer`. Items put before closing may still be queued.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// queue.put(1).unwrap();
+    /// assert!(!queue.is_closed());
+    ///
+    /// queue.close();
+    /// assert!(queue.is_closed());
+    /// assert_eq!(queue.len(), 1);
+    ///
+    /// let queue: FifoQueue<i32> = FifoQueue::new(None);
+    /// drop(queue.producer());
+    /// assert!(queue.is_closed());
+    /// ```
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    /// Puts items from `iter` under a single lock until the queue is full or refuses an
+    /// item, as `put` would with `QueueError::PriorityCap`, returning the iterator
+    /// positioned at the first item which was not put. Items whose key is already queued
+    /// are dropped, as by `put`.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(3));
+    ///
+    /// let leftover = queue.put_from_iter(0..10);
+    /// assert_eq!(leftover.collect::<Vec<_>>(), (3..10).collect::<Vec<_>>());
+    /// assert_eq!(queue.len(), 3);
+    /// assert_eq!(queue.get().unwrap(), 0);
+    /// ```
+    ///
+    /// Batch puts stop at the bound even while other threads put, take and resize.
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(8));
+    ///
+    /// thread::scope(|s| {
+    ///     for _ in 0..2 {
+    ///         s.spawn(|| {
+    ///             for i in 0..2000 {
+    ///                 let _ = queue.put(i);
+    ///                 queue.put_from_iter(0..5);
+    ///             }
+    ///         });
+    ///         s.spawn(|| {
+    ///             for _ in 0..2000 {
+    ///                 let _ = queue.get_batch_wait(1, 4, time::Duration::from_micros(100));
+    ///             }
+    ///         });
+    ///     }
+    ///     s.spawn(|| {
+    ///         for i in 0..2000 {
+    ///             queue.set_maxsize_evict(4 + i % 13);
+    ///         }
+    ///     });
+    ///     s.spawn(|| {
+    ///         for _ in 0..5000 {
+    ///             let guard = queue.lock();
+    ///             assert!(guard.len() <= queue.maxsize().unwrap());
+    ///         }
+    ///     });
+    /// });
+    /// ```
+    ///
+    /// Putting `k` items wakes at most `k` blocked consumers; the others sleep on.
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// let wakeups = Arc::new(AtomicUsize::new(0));
+    /// let consumers: Vec<_> = (0..5)
+    ///     .map(|_| {
+    ///         let consumer = queue.clone();
+    ///         let wakeups = Arc::clone(&wakeups);
+    ///         thread::spawn(move || {
+    ///             consumer.get_wait_observed(time::Duration::from_millis(2000), |_| {
+    ///                 wakeups.fetch_add(1, Ordering::SeqCst);
+    ///             })
+    ///         })
+    ///     })
+    ///     .collect();
+    /// while queue.waiting_consumers() < 5 {
+    ///     thread::sleep(time::Duration::from_millis(1));
+    /// }
+    ///
+    /// queue.put_from_iter(0..2);
+    /// while queue.waiting_consumers() > 3 {
+    ///     thread::sleep(time::Duration::from_millis(1));
+    /// }
+    /// thread::sleep(time::Duration::from_millis(50));
+    /// assert_eq!(queue.waiting_consumers(), 3);
+    /// assert_eq!(wakeups.load(Ordering::SeqCst), 0);
+    ///
+    /// queue.put_from_iter(2..5);
+    /// let mut taken: Vec<_> = consumers.into_iter().map(|th| th.join().unwrap().unwrap()).collect();
+    /// taken.sort();
+    /// assert_eq!(taken, vec![0, 1, 2, 3, 4]);
+    /// ```
+    pub fn put_from_iter<I: IntoIterator<Item = T>>(&self, iter: I) -> Peekable<I::IntoIter> {
+        let mut iter = iter.into_iter().peekable();
+        if self.inner.is_closed() {
+            return iter;
+        }
+        let mut queue = self.inner.queue.lock().unwrap();
+        let mut count = 0;
+        // The checks follow `insert_until`, leaving a refused item for the caller.
+        while let Some(next) = iter.peek() {
+            if queue.is_duplicate(next) {
+                iter.next();
+                continue;
+            }
+            if self.inner.is_full_at(queue.len()) || !queue.admits(next) {
+                break;
+            }
+            if let Some(bucket) = &self.inner.rate_limit {
+                if !bucket.lock().unwrap().try_acquire() {
+                    break;
+                }
+            }
+            queue.put(iter.next().unwrap());
+            count += 1;
+        }
+        self.inner.debug_assert_within_bound(queue.len());
+        if count > 0 {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        if count > 0 {
+            self.inner.notify_not_empty_count(count);
+        }
+        iter
+    }
+
+    /// Moves up to `count` items from this queue to `dst` in dequeue order, stopping early
+    /// if `dst` fills up or refuses an item, or this queue runs empty, and returns the
+    /// number moved. Items whose key is already queued in `dst` are dropped, as by `put`. Both
+    /// queues are locked for the whole move, always in the same order, so two threads
+    /// transferring in opposite directions can't deadlock.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let src = FifoQueue::new(Some(10));
+    /// let dst = FifoQueue::new(Some(3));
+    /// for i in 0..6 {
+    ///     src.put(i).unwrap();
+    /// }
+    /// dst.put(100).unwrap();
+    ///
+    /// assert_eq!(src.transfer(&dst, 5), 2);
+    /// assert_eq!(src.len(), 4);
+    /// assert!(dst.is_full());
+    /// assert_eq!(dst.get().unwrap(), 100);
+    /// assert_eq!(dst.get().unwrap(), 0);
+    /// assert_eq!(dst.get().unwrap(), 1);
+    ///
+    /// assert_eq!(src.transfer(&dst, 10), 3);
+    /// assert_eq!(src.len(), 1);
+    /// assert_eq!(dst.transfer(&src, 10), 3);
+    /// assert_eq!(src.len(), 4);
+    /// assert_eq!(src.get().unwrap(), 5);
+    /// ```
+    pub fn transfer(&self, dst: &Self, count: usize) -> usize {
+        if Arc::ptr_eq(&self.inner, &dst.inner) || dst.inner.is_closed() {
+            return 0;
+        }
+        let (mut src_queue, mut dst_queue) = if Arc::as_ptr(&self.inner) < Arc::as_ptr(&dst.inner) {
+            let src_queue = self.inner.queue.lock().unwrap();
+            (src_queue, dst.inner.queue.lock().unwrap())
+        } else {
+            let dst_queue = dst.inner.queue.lock().unwrap();
+            (self.inner.queue.lock().unwrap(), dst_queue)
+        };
+        let (mut moved, mut put) = (0, 0);
+        while moved < count {
+            let Some(value) = src_queue.get() else {
+                break;
+            };
+            // A duplicate is dropped, as `put` would drop it, but counts as moved.
+            if dst_queue.is_duplicate(&value) {
+                moved += 1;
+                continue;
+            }
+            if dst.inner.is_full_at(dst_queue.len()) || !dst_queue.admits(&value) {
+                src_queue.requeue(value);
+                break;
+            }
+            if let Some(bucket) = &dst.inner.rate_limit {
+                if !bucket.lock().unwrap().try_acquire() {
+                    src_queue.requeue(value);
+                    break;
+                }
+            }
+            dst_queue.put(value);
+            moved += 1;
+            put += 1;
+        }
+        dst.inner.debug_assert_within_bound(dst_queue.len());
+        if moved > 0 {
+            self.inner.record_len(src_queue.len());
+        }
+        if put > 0 {
+            dst.inner.record_len(dst_queue.len());
+        }
+        drop(dst_queue);
+        drop(src_queue);
+        if moved > 0 {
+            self.inner.notify_all_not_full();
+        }
+        if put > 0 {
+            dst.inner.notify_not_empty_count(put);
+        }
+        moved
+    }
+
+    /// Moves items into `dst`, transformed by `f`, until this queue is closed and drained,
+    /// or shut down, and returns how many were moved. Each wait is bounded by `timeout`: a get which
+    /// times out is simply retried, while a put which can't complete in time stops the
+    /// pour and hands back the transformed item.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, LifoQueue, Queue};
+    ///
+    /// let src = FifoQueue::new(Some(2));
+    /// let dst = LifoQueue::new(None);
+    ///
+    /// let producer = src.clone();
+    /// let th = thread::spawn(move || {
+    ///     for i in 0..10 {
+    ///         producer.put_wait(i, time::Duration::from_millis(1000)).unwrap();
+    ///     }
+    ///     producer.close();
+    /// });
+    ///
+    /// let moved = src.pour(&dst, |i| i.to_string(), time::Duration::from_millis(10));
+    /// assert_eq!(moved.unwrap(), 10);
+    /// th.join().unwrap();
+    ///
+    /// let items: Vec<_> = (0..10).map(|_| dst.get().unwrap()).collect();
+    /// let expected: Vec<_> = (0..10).rev().map(|i: i32| i.to_string()).collect();
+    /// assert_eq!(items, expected);
+    /// ```
+    pub fn pour<Q2: BasicArray<U>, U>(
+        &self,
+        dst: &BaseQueue<Q2, U>,
+        f: impl Fn(T) -> U,
+        timeout: time::Duration,
+    ) -> Result<usize, PutError<U>> {
+        let mut count = 0;
+        loop {
+            match self.get_wait(timeout) {
+                Ok(value) => dst.put_wait(f(value), timeout)?,
+                Err(QueueError::Empty) => continue,
+                // Closed and drained, or shut down.
+                Err(_) => return Ok(count),
+            }
+            count += 1;
+        }
+    }
+
+    /// How many items the queue can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.inner.queue.lock().unwrap().capacity()
+    }
+
+    /// Releases memory the queue kept from holding more items than it does now, e.g.
+    /// after a burst has drained.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, LifoQueue, PrioritizedItem, PriorityQueue, Queue};
+    ///
+    /// let fifo = FifoQueue::new(None);
+    /// let lifo = LifoQueue::new(None);
+    /// let priority = PriorityQueue::new(None);
+    /// for i in 0..1000 {
+    ///     fifo.put(i).unwrap();
+    ///     lifo.put(i).unwrap();
+    ///     priority.put(PrioritizedItem(i, i)).unwrap();
+    /// }
+    /// for _ in 0..990 {
+    ///     fifo.get().unwrap();
+    ///     lifo.get().unwrap();
+    ///     priority.get().unwrap();
+    /// }
+    /// assert!(fifo.capacity() >= 1000);
+    ///
+    /// fifo.compact();
+    /// lifo.compact();
+    /// priority.compact();
+    /// assert!(fifo.capacity() < 1000 && fifo.capacity() >= 10);
+    /// assert!(lifo.capacity() < 1000 && lifo.capacity() >= 10);
+    /// assert!(priority.capacity() < 1000 && priority.capacity() >= 10);
+    /// assert_eq!(fifo.get().unwrap(), 990);
+    /// ```
+    pub fn compact(&self) {
+        self.inner.queue.lock().unwrap().shrink_to_fit();
+    }
+
+    /// Like `compact`, but only if fewer than `threshold` of the allocated slots are in
+    /// use, so that a queue which is merely between bursts keeps its memory. Returns
+    /// whether the queue was compacted.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for i in 0..100 {
+    ///     queue.put(i).unwrap();
+    /// }
+    /// for _ in 0..10 {
+    ///     queue.get().unwrap();
+    /// }
+    /// assert!(!queue.compact_if(0.5));
+    /// assert!(queue.capacity() >= 100);
+    ///
+    /// for _ in 0..80 {
+    ///     queue.get().unwrap();
+    /// }
+    /// assert!(queue.compact_if(0.5));
+    /// assert!(queue.capacity() < 100);
+    /// ```
+    pub fn compact_if(&self, threshold: f64) -> bool {
+        let mut queue = self.inner.queue.lock().unwrap();
+        if (queue.len() as f64) < threshold * queue.capacity() as f64 {
+            queue.shrink_to_fit();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Takes items in dequeue order while both at most `max_count` items and at most
+    /// `max_size` in total, as measured by `size_of`, are taken. Stops at the first item
+    /// which would exceed either cap, leaving it queued.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for message in ["ab", "cde", "f", "ghij", "k"] {
+    ///     queue.put(message).unwrap();
+    /// }
+    ///
+    /// let batch = queue.drain_capped(10, 6, |message| message.len());
+    /// assert_eq!(batch, vec!["ab", "cde", "f"]);
+    ///
+    /// let batch = queue.drain_capped(1, 100, |message| message.len());
+    /// assert_eq!(batch, vec!["ghij"]);
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    pub fn drain_capped(
+        &self,
+        max_count: usize,
+        max_size: usize,
+        size_of: impl Fn(&T) -> usize,
+    ) -> Vec<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let mut batch = Vec::new();
+        let mut size = 0;
+        while batch.len() < max_count {
+            let Some(next) = queue.peek() else {
+                break;
+            };
+            match size_of(next).checked_add(size) {
+                Some(total) if total <= max_size => size = total,
+                _ => break,
+            }
+            match queue.get() {
+                Some(value) => batch.push(value),
+                None => break,
+            }
+        }
+        if !batch.is_empty() {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        if !batch.is_empty() {
+            self.inner.notify_all_not_full();
+        }
+        batch
+    }
+
+    /// Takes every queued item, in the order `get` would take them. The queue lock is only
+    /// held to swap in an empty store, and the items are taken from the old one after it
+    /// is released.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{LifoQueue, Queue};
+    ///
+    /// let queue = LifoQueue::new(Some(3));
+    /// for i in 0..3 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// assert_eq!(queue.take_all(), vec![2, 1, 0]);
+    /// assert!(queue.is_empty());
+    /// assert!(queue.take_all().is_empty());
+    /// queue.put(3).unwrap();
+    /// ```
+    pub fn take_all(&self) -> Vec<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let empty = queue.new_empty(self.inner.maxsize());
+        let mut taken = std::mem::replace(&mut *queue, empty);
+        if taken.len() > 0 {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        let items: Vec<T> = std::iter::from_fn(|| taken.get()).collect();
+        if !items.is_empty() {
+            self.inner.notify_all_not_full();
+        }
+        items
+    }
+
+    /// Takes every queued item and returns them sorted by `key`. Items with equal keys
+    /// keep the order they would have been taken in.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for word in ["pear", "fig", "apple", "kiwi"] {
+    ///     queue.put(word).unwrap();
+    /// }
+    ///
+    /// assert_eq!(queue.drain_sorted_by_key(|word| word.len()), vec!["fig", "pear", "kiwi", "apple"]);
+    /// assert!(queue.is_empty());
+    /// ```
+    pub fn drain_sorted_by_key<K: Ord>(&self, key: impl FnMut(&T) -> K) -> Vec<T> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let mut items: Vec<T> = std::iter::from_fn(|| queue.get()).collect();
+        if !items.is_empty() {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        if !items.is_empty() {
+            self.inner.notify_all_not_full();
+        }
+        items.sort_by_key(key);
+        items
+    }
+
+    /// Takes items and sends them into `tx` until the queue is empty, returning how many
+    /// were sent. If the receiver has hung up, the item which could not be sent is handed
+    /// back in the error and the remaining items stay queued.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::mpsc;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for i in 0..3 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// let (tx, rx) = mpsc::channel();
+    /// assert_eq!(queue.drain_into(&tx), Ok(3));
+    /// assert!(queue.is_empty());
+    /// assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+    ///
+    /// queue.put(3).unwrap();
+    /// queue.put(4).unwrap();
+    /// drop(rx);
+    /// assert_eq!(queue.drain_into(&tx), Err(mpsc::SendError(3)));
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    pub fn drain_into(&self, tx: &mpsc::Sender<T>) -> Result<usize, mpsc::SendError<T>> {
+        let mut count = 0;
+        while let Ok(value) = self.get() {
+            tx.send(value)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Returns how full a bounded queue is, from `0.0` to `1.0`, or `None` if the queue is
+    /// unbounded.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(4));
+    /// queue.put(1).unwrap();
+    /// queue.put(2).unwrap();
+    /// assert_eq!(queue.utilization(), Some(0.5));
+    ///
+    /// let queue: FifoQueue<i32> = FifoQueue::new(None);
+    /// assert_eq!(queue.utilization(), None);
+    /// ```
+    pub fn utilization(&self) -> Option<f64> {
+        let queue = self.inner.queue.lock().unwrap();
+        match self.inner.maxsize()? {
+            0 => Some(1.0),
+            maxsize => Some(queue.len() as f64 / maxsize as f64),
+        }
+    }
+
+    /// Blocks until the queue holds at most `watermark` items. Returns `QueueError::Full`
+    /// if the queue is still above the watermark when `timeout` elapses. A zero `timeout`
+    /// waits without limit.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for i in 0..5 {
+    ///     queue.put(i).unwrap();
+    /// }
+    /// assert!(queue.flush(2, time::Duration::from_millis(10)).is_err());
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     for _ in 0..3 {
+    ///         thread::sleep(time::Duration::from_millis(10));
+    ///         consumer.get().unwrap();
+    ///     }
+    /// });
+    ///
+    /// queue.flush(2, time::Duration::from_millis(1000)).unwrap();
+    /// assert!(queue.len() <= 2);
+    /// th.join().unwrap();
+    /// ```
+    pub fn flush(&self, watermark: usize, timeout: time::Duration) -> Result<(), QueueError> {
+        let drained = self.inner.wait_until(
+            &self.inner.drained,
+            || self.inner.queue.lock().unwrap().len() <= watermark,
+            timeout,
+        );
+        if self.inner.is_shut_down() {
+            return Err(QueueError::ShutDown);
+        }
+        match drained {
+            true => Ok(()),
+            false => Err(QueueError::Full),
+        }
+    }
+
+    /// Blocks until there is room for at least one more item, and returns how many items
+    /// would fit now. An unbounded queue returns `usize::MAX` at once. Fails with
+    /// `QueueError::Full` if `timeout` elapses first, or `QueueError::Closed` if the queue
+    /// is closed. A zero `timeout` waits without limit.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(Some(5));
+    /// queue.put_from_iter(0..5);
+    /// assert_eq!(queue.wait_for_space(time::Duration::from_millis(10)), Err(QueueError::Full));
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     thread::sleep(time::Duration::from_millis(20));
+    ///     consumer.get_batch_wait(3, 3, time::Duration::ZERO).unwrap()
+    /// });
+    /// assert_eq!(queue.wait_for_space(time::Duration::from_millis(1000)), Ok(3));
+    /// assert_eq!(th.join().unwrap(), vec![0, 1, 2]);
+    ///
+    /// let unbounded: FifoQueue<i32> = FifoQueue::new(None);
+    /// assert_eq!(unbounded.wait_for_space(time::Duration::ZERO), Ok(usize::MAX));
+    /// ```
+    pub fn wait_for_space(&self, timeout: time::Duration) -> Result<usize, QueueError> {
+        let room = || {
+            let queue = self.inner.queue.lock().unwrap();
+            let reserved = self.inner.reserved.load(Ordering::SeqCst)
+                + self.inner.reserved_slots.load(Ordering::SeqCst);
+            match self.inner.maxsize() {
+                None => usize::MAX,
+                Some(maxsize) => maxsize.saturating_sub(queue.len() + reserved),
+            }
+        };
+        // `drained` is notified on every take, unlike `not_full`, which may wake only the
+        // producer next in line.
+        let ready = self.inner.wait_until(
+            &self.inner.drained,
+            || room() > 0 || self.inner.is_closed(),
+            timeout,
+        );
+        if self.inner.is_shut_down() {
+            return Err(QueueError::ShutDown);
+        }
+        if self.inner.is_closed() {
+            return Err(QueueError::Closed);
+        }
+        match ready {
+            true => Ok(room()),
+            false => Err(QueueError::Full),
+        }
+    }
+
+    /// Blocks until at least `min` items are queued, then takes up to `max` of them.
+    ///
+    /// If `timeout` elapses, or the queue is closed, before `min` items are queued, the
+    /// items queued so far are taken instead; `QueueError::Empty` (or `QueueError::Closed`)
+    /// is returned only if there are none. A zero `timeout` waits without limit.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for i in 0..5 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// let batch = queue.get_batch_wait(2, 3, time::Duration::from_millis(100)).unwrap();
+    /// assert_eq!(batch, vec![0, 1, 2]);
+    ///
+    /// let batch = queue.get_batch_wait(3, 3, time::Duration::from_millis(10)).unwrap();
+    /// assert_eq!(batch, vec![3, 4]);
+    ///
+    /// let err = queue.get_batch_wait(1, 3, time::Duration::from_millis(10)).unwrap_err();
+    /// assert!(matches!(err, QueueError::Empty));
+    /// ```
+    pub fn get_batch_wait(
+        &self,
+        min: usize,
+        max: usize,
+        timeout: time::Duration,
+    ) -> Result<Vec<T>, QueueError> {
+        let waiting = Waiting::new(&self.inner.waiting_consumers);
+        self.inner.wait_until(
+            &self.inner.not_empty,
+            || self.len() >= min || self.inner.is_closed(),
+            timeout,
+        );
+        drop(waiting);
+        if self.inner.is_shut_down() {
+            return Err(QueueError::ShutDown);
+        }
+        let mut queue = self.inner.queue.lock().unwrap();
+        let mut batch = Vec::with_capacity(max.min(queue.len()));
+        while batch.len() < max {
+            match queue.get() {
+                Some(value) => batch.push(value),
+                None => break,
+            }
+        }
+        if !batch.is_empty() {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        if !batch.is_empty() {
+            self.inner.notify_all_not_full();
+            Ok(batch)
+        } else if self.inner.is_closed() {
+            Err(QueueError::Closed)
+        } else {
+            Err(QueueError::Empty)
+        }
+    }
+
+    /// Takes items until none arrives for `idle`, or until `max_total` has elapsed, and
+    /// returns them as a batch.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    ///
+    /// let producer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     for i in 0..3 {
+    ///         producer.put(i).unwrap();
+    ///         thread::sleep(time::Duration::from_millis(5));
+    ///     }
+    ///     thread::sleep(time::Duration::from_millis(200));
+    ///     producer.put(3).unwrap();
+    /// });
+    ///
+    /// let batch = queue.drain_until_idle(
+    ///     time::Duration::from_millis(50),
+    ///     time::Duration::from_millis(1000),
+    /// );
+    /// assert_eq!(batch, vec![0, 1, 2]);
+    /// th.join().unwrap();
+    /// assert_eq!(queue.get().unwrap(), 3);
+    /// ```
+    pub fn drain_until_idle(&self, idle: time::Duration, max_total: time::Duration) -> Vec<T> {
+        let timestamp = time::Instant::now();
+        let mut batch = Vec::new();
+        loop {
+            let wait = idle.min(max_total.saturating_sub(timestamp.elapsed()));
+            if wait.is_zero() {
+                return batch;
+            }
+            match self.get_wait(wait) {
+                Ok(value) => batch.push(value),
+                Err(_) => return batch,
+            }
+        }
+    }
+
+    /// Takes up to `max` items, waiting for more to arrive until `budget` has elapsed in
+    /// total, and returns what it gathered, possibly nothing. The budget covers the whole
+    /// call rather than each item. A zero `budget` takes only the items already queued.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    ///
+    /// let producer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     for i in 0..10 {
+    ///         producer.put(i).unwrap();
+    ///         thread::sleep(time::Duration::from_millis(30));
+    ///     }
+    /// });
+    ///
+    /// let timestamp = time::Instant::now();
+    /// let batch = queue.get_up_to(10, time::Duration::from_millis(100));
+    /// let elapsed = timestamp.elapsed();
+    /// assert!(elapsed >= time::Duration::from_millis(100));
+    /// assert!(elapsed < time::Duration::from_millis(250));
+    /// assert!(!batch.is_empty() && batch.len() < 10);
+    /// assert_eq!(batch, (0..batch.len() as i32).collect::<Vec<_>>());
+    /// th.join().unwrap();
+    ///
+    /// assert_eq!(queue.get_up_to(3, time::Duration::ZERO).len(), 3);
+    /// ```
+    pub fn get_up_to(&self, max: usize, budget: time::Duration) -> Vec<T> {
+        let deadline = time::Instant::now() + budget;
+        let mut batch = Vec::new();
+        while batch.len() < max {
+            let taken = batch.len();
+            let mut queue = self.inner.queue.lock().unwrap();
+            while batch.len() < max {
+                match queue.get() {
+                    Some(value) => batch.push(value),
+                    None => break,
+                }
+            }
+            if batch.len() > taken {
+                self.inner.record_len(queue.len());
+            }
+            drop(queue);
+            if batch.len() > taken {
+                self.inner.notify_all_not_full();
+            }
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
+            if batch.len() >= max || remaining.is_zero() || self.inner.is_closed() {
+                break;
+            }
+            let _waiting = Waiting::new(&self.inner.waiting_consumers);
+            let arrived = self.inner.wait_until(
+                &self.inner.not_empty,
+                || !self.is_empty() || self.inner.is_closed(),
+                remaining,
+            );
+            if !arrived {
+                break;
+            }
+        }
+        batch
+    }
+
+    /// Returns a function putting each item it is called with, blocking while the queue is
+    /// full, so that an iterator can be piped into the queue with `try_for_each`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(2));
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     (0..10)
+    ///         .map(|_| consumer.get_wait(time::Duration::from_millis(1000)).unwrap())
+    ///         .collect::<Vec<_>>()
+    /// });
+    ///
+    /// (0..10).try_for_each(queue.sink()).unwrap();
+    /// assert_eq!(th.join().unwrap(), (0..10).collect::<Vec<_>>());
+    /// ```
+    pub fn sink(&self) -> impl FnMut(T) -> Result<(), PutError<T>> {
+        let queue = self.clone();
+        move |value| queue.put_wait(value, time::Duration::ZERO)
+    }
+
+    /// Whether a thread panicked while holding the queue lock. Operations on a poisoned
+    /// queue panic until `clear_poison` is called.
+    pub fn is_poisoned(&self) -> bool {
+        self.inner.queue.is_poisoned() || self.inner.pending.is_poisoned()
+    }
+
+    /// Clears the poisoned state left by a panic while holding the queue lock, for callers
+    /// which have decided the queued items are still fine to use.
+    ///
+    /// # Example
+    /// ```
+    /// use std::panic;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// queue.put(1).unwrap();
+    ///
+    /// let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+    ///     queue.put_from_iter((2..4).map(|i| if i == 3 { panic!() } else { i }));
+    /// }));
+    /// assert!(result.is_err());
+    /// assert!(queue.is_poisoned());
+    ///
+    /// queue.clear_poison();
+    /// assert!(!queue.is_poisoned());
+    /// queue.put(4).unwrap();
+    /// assert_eq!(queue.get().unwrap(), 1);
+    /// assert_eq!(queue.get().unwrap(), 2);
+    /// assert_eq!(queue.get().unwrap(), 4);
+    /// ```
+    pub fn clear_poison(&self) {
+        self.inner.queue.clear_poison();
+        self.inner.pending.clear_poison();
+    }
+
+    /// Returns a producer handle. Once every producer handle taken from the queue has
+    /// been dropped, the queue is closed.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue, QueueError};
+    ///
+    /// let queue = FifoQueue::new(None);
+    ///
+    /// let producer1 = queue.producer();
+    /// let producer2 = producer1.clone();
+    /// let th1 = thread::spawn(move || producer1.put(1).unwrap());
+    /// let th2 = thread::spawn(move || producer2.put(2).unwrap());
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     let mut items = Vec::new();
+    ///     loop {
+    ///         match consumer.get_wait(time::Duration::from_millis(5000)) {
+    ///             Ok(item) => items.push(item),
+    ///             Err(err) => return (items, err),
+    ///         }
+    ///     }
+    /// });
+    /// th1.join().unwrap();
+    /// th2.join().unwrap();
+    ///
+    /// let (mut items, err) = th.join().unwrap();
+    /// items.sort();
+    /// assert_eq!(items, vec![1, 2]);
+    /// assert!(matches!(err, QueueError::Closed));
+    /// ```
+    pub fn producer(&self) -> Producer<Q, T> {
+        Producer::new(self.clone())
+    }
+}
+
+impl<Q: BasicArray<T>, T> Queue<T> for BaseQueue<Q, T> {
+    fn len(&self) -> usize {
+        self.inner.queue.lock().unwrap().len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn is_full(&self) -> bool {
+        self.inner.is_full_at(self.len())
+    }
+
+    fn is_bounded(&self) -> bool {
+        self.inner.maxsize().is_some()
+    }
+
+    fn maxsize(&self) -> Option<usize> {
+        self.inner.maxsize()
+    }
+
+    fn get(&self) -> Result<T, QueueError> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let value = queue.get();
+        let len = queue.len();
+        if value.is_some() {
+            self.inner.record_len(len);
+        }
+        drop(queue);
+        match value {
+            Some(value) => {
+                trace_event!(len, "get");
+                audit_event!(self.inner, AuditOp::Get, len);
+                self.inner.notify_not_full();
+                Ok(value)
+            }
+            None if self.inner.is_closed() => Err(QueueError::Closed),
+            None => {
+                trace_event!(len, "empty");
+                Err(QueueError::Empty)
+            }
+        }
+    }
+
+    fn get_wait(&self, timeout: time::Duration) -> Result<T, QueueError> {
+        self.get_wait_observed(timeout, |_| {})
+    }
+
+    fn len_at_least(&self, n: usize) -> bool {
+        self.inner.queue.lock().unwrap().len_at_least(n)
+    }
+
+    fn len_at_most(&self, n: usize) -> bool {
+        self.inner.queue.lock().unwrap().len_at_most(n)
+    }
+
+    fn put(&self, value: T) -> Result<(), PutError<T>> {
+        self.insert(value).map(|_| ())
+    }
+
+    /// Producers blocked on a full queue are let in one at a time, in the order they
+    /// started waiting. A `put_wait` which finds producers already waiting joins the end
+    /// of the line rather than putting ahead of them; a plain `put` doesn't wait its turn.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(1));
+    /// queue.put(0).unwrap();
+    ///
+    /// let mut producers = Vec::new();
+    /// for i in 1..=5 {
+    ///     let producer = queue.clone();
+    ///     producers.push(thread::spawn(move || {
+    ///         producer.put_wait(i, time::Duration::from_millis(5000))
+    ///     }));
+    ///     while queue.waiting_producers() < i {
+    ///         thread::sleep(time::Duration::from_millis(1));
+    ///     }
+    /// }
+    ///
+    /// let admitted: Vec<_> = (0..=5)
+    ///     .map(|_| queue.get_wait(time::Duration::from_millis(5000)).unwrap())
+    ///     .collect();
+    /// assert_eq!(admitted, vec![0, 1, 2, 3, 4, 5]);
+    /// for producer in producers {
+    ///     producer.join().unwrap().unwrap();
+    /// }
+    /// ```
+    fn put_wait(&self, mut value: T, timeout: time::Duration) -> Result<(), PutError<T>> {
+        let timestamp = time::Instant::now();
+        let deadline = (!timeout.is_zero()).then(|| timestamp + timeout);
+        let mut fruitless = 0;
+        let mut ticket: Option<Ticket> = None;
+        loop {
+            if self.inner.is_shut_down() {
+                return Err(PutError(value, QueueError::ShutDown));
+            }
+            let admitted = self.inner.is_closed()
+                || match &ticket {
+                    None => self.inner.producer_line.is_empty(),
+                    Some(ticket) => ticket.is_first(),
+                };
+            if admitted {
+                match self.insert_until(value, deadline, false) {
+                    Err(PutError(rejected, QueueError::Full)) => value = rejected,
+                    Err(PutError(rejected, QueueError::RateLimited)) => {
+                        let delay = match &self.inner.rate_limit {
+                            Some(bucket) => bucket.lock().unwrap().time_until_token(),
+                            None => time::Duration::ZERO,
+                        };
+                        if !timeout.is_zero() && timestamp.elapsed() + delay > timeout {
+                            return Err(PutError(rejected, QueueError::RateLimited));
+                        }
+                        thread::sleep(delay);
+                        value = rejected;
+                        continue;
+                    }
+                    ret => return ret.map(|_| ()),
+                }
+            }
+            let remaining = match timeout.is_zero() {
+                true => time::Duration::ZERO,
+                false => match timeout.checked_sub(timestamp.elapsed()) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    _ => return Err(PutError(value, QueueError::Full)),
+                },
+            };
+            if fruitless > 0 {
+                backoff(fruitless);
+            }
+            let ticket = ticket.get_or_insert_with(|| Ticket::new(&self.inner));
+            let _waiting = Waiting::new(&self.inner.waiting_producers);
+            let ready = self.inner.wait_until(
+                &self.inner.not_full,
+                || (ticket.is_first() && !self.is_full()) || self.inner.is_closed(),
+                remaining,
+            );
+            if self.inner.is_shut_down() {
+                return Err(PutError(value, QueueError::ShutDown));
+            }
+            if !ready {
+                return Err(PutError(value, QueueError::Full));
+            }
+            fruitless += 1;
+        }
+    }
+}
+
+impl<Q, T> Clone for BaseQueue<Q, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Inner = QueueInner<VecDeque<i32>, i32>;
+
+    /// Sets `flag` and notifies `not_empty` from another thread after `delay`.
+    fn notify_after(
+        inner: &Arc<Inner>,
+        flag: &Arc<AtomicBool>,
+        delay: time::Duration,
+    ) -> thread::JoinHandle<()> {
+        let inner = Arc::clone(inner);
+        let flag = Arc::clone(flag);
+        thread::spawn(move || {
+            thread::sleep(delay);
+            flag.store(true, Ordering::SeqCst);
+            let _pending = inner.pending.lock().unwrap();
+            inner.not_empty.notify_all();
+        })
+    }
+
+    #[test]
+    fn wait_until_returns_at_once_if_pred_holds() {
+        let inner = Inner::new(None);
+        let timestamp = time::Instant::now();
+        assert!(inner.wait_until(&inner.not_empty, || true, time::Duration::from_secs(10)));
+        assert!(timestamp.elapsed() < time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn wait_until_wakes_on_notify() {
+        let inner = Arc::new(Inner::new(None));
+        let flag = Arc::new(AtomicBool::new(false));
+        let th = notify_after(&inner, &flag, time::Duration::from_millis(20));
+        let timestamp = time::Instant::now();
+        assert!(inner.wait_until(
+            &inner.not_empty,
+            || flag.load(Ordering::SeqCst),
+            time::Duration::from_secs(10),
+        ));
+        assert!(timestamp.elapsed() < time::Duration::from_secs(5));
+        th.join().unwrap();
+    }
+
+    #[test]
+    fn wait_until_gives_up_at_timeout() {
+        let inner = Inner::new(None);
+        let timeout = time::Duration::from_millis(30);
+        let timestamp = time::Instant::now();
+        assert!(!inner.wait_until(&inner.not_empty, || false, timeout));
+        assert!(timestamp.elapsed() >= timeout);
+    }
+
+    #[test]
+    fn wait_until_zero_timeout_waits_without_limit() {
+        let inner = Arc::new(Inner::new(None));
+        let flag = Arc::new(AtomicBool::new(false));
+        let delay = time::Duration::from_millis(50);
+        let th = notify_after(&inner, &flag, delay);
+        let timestamp = time::Instant::now();
+        assert!(inner.wait_until(
+            &inner.not_empty,
+            || flag.load(Ordering::SeqCst),
+            time::Duration::ZERO,
+        ));
+        assert!(timestamp.elapsed() >= delay);
+        th.join().unwrap();
+    }
 }
@@ -0,0 +1,235 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time;
+
+use crate::queue::*;
+
+/// A wakeup shared by several queues. Each notify starts a new epoch, so a waiter which
+/// read the epoch before checking the queues, without holding `pending`, still can't
+/// miss a notify sent after its check.
+pub(crate) struct GroupSignal {
+    pub(crate) pending: Mutex<()>,
+    pub(crate) available: Condvar,
+    epoch: AtomicU64,
+    waiting: AtomicUsize,
+}
+
+impl GroupSignal {
+    pub(crate) fn new() -> Self {
+        Self {
+            pending: Mutex::new(()),
+            available: Condvar::new(),
+            epoch: AtomicU64::new(0),
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    /// Wakes as many waiters as `count` changes can serve, or all of them if there are no
+    /// more than `count`.
+    pub(crate) fn notify(&self, count: usize) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+        let _pending = self.pending.lock().unwrap();
+        if count >= self.waiting.load(Ordering::SeqCst) {
+            self.available.notify_all();
+        } else {
+            for _ in 0..count {
+                self.available.notify_one();
+            }
+        }
+    }
+
+    /// Waits until the epoch has moved on from `seen`, or `deadline` passes, and returns
+    /// whether it moved on in time.
+    pub(crate) fn wait_since(&self, seen: u64, deadline: Option<time::Instant>) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        let _waiting = Waiting::new(&self.waiting);
+        while self.epoch() == seen {
+            pending = match deadline {
+                None => self.available.wait(pending).unwrap(),
+                Some(deadline) => match deadline.checked_duration_since(time::Instant::now()) {
+                    Some(remaining) if !remaining.is_zero() => {
+                        self.available.wait_timeout(pending, remaining).unwrap().0
+                    }
+                    _ => return false,
+                },
+            };
+        }
+        true
+    }
+}
+
+/// A set of queues sharing one wakeup, so a consumer can block until any member has an item.
+///
+/// # Example
+/// ```
+/// use std::thread;
+/// use std::time;
+///
+/// use rueue::{FifoQueue, LifoQueue, Queue, QueueGroup};
+///
+/// let fifo = FifoQueue::new(None);
+/// let lifo = LifoQueue::new(None);
+///
+/// let mut group = QueueGroup::new();
+/// group.register(&fifo);
+/// group.register(&lifo);
+///
+/// let th = thread::spawn(move || group.get_any_wait(time::Duration::from_millis(1000)));
+/// thread::sleep(time::Duration::from_millis(10));
+/// lifo.put(1).unwrap();
+///
+/// assert_eq!(th.join().unwrap().unwrap(), 1);
+/// assert!(fifo.is_empty());
+/// ```
+pub struct QueueGroup<T> {
+    signal: Arc<GroupSignal>,
+    members: Vec<Member<T>>,
+}
+
+struct Member<T> {
+    queue: Box<dyn Queue<T> + Send>,
+    /// Identifies the queue for `unregister`.
+    id: usize,
+    /// Stops the queue from notifying the group.
+    leave: Box<dyn Fn() + Send>,
+}
+
+impl<T: 'static> QueueGroup<T> {
+    pub fn new() -> Self {
+        Self {
+            signal: Arc::new(GroupSignal::new()),
+            members: Vec::new(),
+        }
+    }
+
+    /// Adds a queue to the group. Puts into the queue wake consumers waiting on the group.
+    pub fn register<Q>(&mut self, queue: &BaseQueue<Q, T>)
+    where
+        Q: BasicArray<T> + 'static,
+        BaseQueue<Q, T>: Send,
+    {
+        queue
+            .inner
+            .groups
+            .lock()
+            .unwrap()
+            .push(Arc::clone(&self.signal));
+        let member = queue.clone();
+        let signal = Arc::clone(&self.signal);
+        self.members.push(Member {
+            queue: Box::new(queue.clone()),
+            id: Arc::as_ptr(&queue.inner) as usize,
+            leave: Box::new(move || {
+                let queue = &member;
+                let mut groups = queue.inner.groups.lock().unwrap();
+                groups.retain(|group| !Arc::ptr_eq(group, &signal));
+            }),
+        });
+    }
+
+    /// Removes a queue from the group, returning whether it was a member. The group is
+    /// also removed from all its queues when it is dropped.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue, QueueError, QueueGroup};
+    ///
+    /// let fifo1 = FifoQueue::new(None);
+    /// let fifo2 = FifoQueue::new(None);
+    ///
+    /// let mut group = QueueGroup::new();
+    /// group.register(&fifo1);
+    /// group.register(&fifo2);
+    ///
+    /// assert!(group.unregister(&fifo1));
+    /// assert!(!group.unregister(&fifo1));
+    /// fifo1.put(1).unwrap();
+    /// assert_eq!(group.get_any(), Err(QueueError::Empty));
+    /// fifo2.put(2).unwrap();
+    /// assert_eq!(group.get_any_wait(time::Duration::from_millis(10)), Ok(2));
+    /// ```
+    pub fn unregister<Q: BasicArray<T>>(&mut self, queue: &BaseQueue<Q, T>) -> bool {
+        let id = Arc::as_ptr(&queue.inner) as usize;
+        let Some(i) = self.members.iter().position(|member| member.id == id) else {
+            return false;
+        };
+        let member = self.members.remove(i);
+        (member.leave)();
+        true
+    }
+
+    /// Takes an item from the first member which has one. Fails with `QueueError::Closed`
+    /// if every member is closed and drained, and with `QueueError::Empty` otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{FifoQueue, Queue, QueueError, QueueGroup};
+    ///
+    /// let fifo1 = FifoQueue::new(None);
+    /// let fifo2 = FifoQueue::new(None);
+    ///
+    /// let mut group = QueueGroup::new();
+    /// group.register(&fifo1);
+    /// group.register(&fifo2);
+    ///
+    /// assert!(group.get_any().is_err());
+    ///
+    /// fifo2.put(1).unwrap();
+    /// assert_eq!(group.get_any().unwrap(), 1);
+    ///
+    /// fifo1.close();
+    /// assert_eq!(group.get_any(), Err(QueueError::Empty));
+    /// fifo2.close();
+    /// assert_eq!(group.get_any(), Err(QueueError::Closed));
+    /// ```
+    pub fn get_any(&self) -> Result<T, QueueError> {
+        let mut closed = 0;
+        for member in &self.members {
+            match member.queue.get() {
+                Ok(value) => return Ok(value),
+                Err(QueueError::Closed) => closed += 1,
+                Err(_) => {}
+            }
+        }
+        match !self.members.is_empty() && closed == self.members.len() {
+            true => Err(QueueError::Closed),
+            false => Err(QueueError::Empty),
+        }
+    }
+
+    /// Like `get_any`, but waits for an item for at most `timeout`, or without limit if it
+    /// is zero. Returns at once with `QueueError::Closed` once every member is closed and
+    /// drained.
+    pub fn get_any_wait(&self, timeout: time::Duration) -> Result<T, QueueError> {
+        let deadline = (!timeout.is_zero()).then(|| time::Instant::now() + timeout);
+        loop {
+            let seen = self.signal.epoch();
+            match self.get_any() {
+                Err(QueueError::Empty) => {}
+                ret => return ret,
+            }
+            if !self.signal.wait_since(seen, deadline) {
+                return Err(QueueError::Empty);
+            }
+        }
+    }
+}
+
+impl<T> Drop for QueueGroup<T> {
+    fn drop(&mut self) {
+        for member in &self.members {
+            (member.leave)();
+        }
+    }
+}
+
+impl<T: 'static> Default for QueueGroup<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
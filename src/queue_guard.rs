@@ -0,0 +1,133 @@
+use std::sync::MutexGuard;
+
+use crate::queue::*;
+
+/// Exclusive access to a queue's items, from `BaseQueue::lock`. Other threads block on the
+/// queue until the guard is dropped, which wakes the consumers and producers its gets and
+/// puts made progress for.
+pub struct QueueGuard<'a, Q: BasicArray<T>, T> {
+    inner: &'a QueueInner<Q, T>,
+    queue: Option<MutexGuard<'a, Q>>,
+    taken: bool,
+    puts: usize,
+}
+
+impl<Q: BasicArray<T>, T> QueueGuard<'_, Q, T> {
+    fn queue(&self) -> &Q {
+        self.queue.as_ref().unwrap()
+    }
+
+    fn queue_mut(&mut self) -> &mut Q {
+        self.queue.as_mut().unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The item the next `get` would return, without taking it.
+    pub fn peek(&self) -> Option<&T> {
+        self.queue().peek()
+    }
+
+    pub fn get(&mut self) -> Option<T> {
+        let value = self.queue_mut().get();
+        if value.is_some() {
+            self.taken = true;
+            self.inner.record_len(self.len());
+        }
+        value
+    }
+
+    /// Puts `value` unless the queue is closed, full, or doesn't admit it. The rate
+    /// limit, if any, does not apply.
+    pub fn put(&mut self, value: T) -> Result<(), PutError<T>> {
+        if self.inner.is_closed() {
+            return Err(PutError(value, QueueError::Closed));
+        }
+        if self.inner.is_full_at(self.len()) {
+            return Err(PutError(value, QueueError::Full));
+        }
+        if !self.queue().admits(&value) {
+            return Err(PutError(value, QueueError::PriorityCap));
+        }
+        self.queue_mut().put(value);
+        self.inner.debug_assert_within_bound(self.len());
+        self.inner.record_len(self.len());
+        self.puts += 1;
+        Ok(())
+    }
+}
+
+impl<Q: BasicArray<T>, T> Drop for QueueGuard<'_, Q, T> {
+    fn drop(&mut self) {
+        drop(self.queue.take());
+        if self.puts > 0 {
+            self.inner.notify_not_empty_count(self.puts);
+        }
+        if self.taken {
+            self.inner.notify_all_not_full();
+        }
+    }
+}
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Locks the queue, so that several operations through the returned guard happen
+    /// without other threads interleaving.
+    ///
+    /// The queue's own methods lock it too, so calling any of them on the same queue
+    /// while the guard is alive, from this thread, deadlocks. Keep the guard short-lived;
+    /// every other user of the queue waits for it.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// // Takes the front item only if it is even. Without the guard, another thread could
+    /// // take the item between the peek and the get.
+    /// fn take_even(queue: &FifoQueue<i32>) -> Option<i32> {
+    ///     let mut guard = queue.lock();
+    ///     match guard.peek() {
+    ///         Some(value) if value % 2 == 0 => guard.get(),
+    ///         _ => None,
+    ///     }
+    /// }
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// queue.put(2).unwrap();
+    /// queue.put(3).unwrap();
+    /// assert_eq!(take_even(&queue), Some(2));
+    /// assert_eq!(take_even(&queue), None);
+    /// assert_eq!(queue.len(), 1);
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// for _ in 0..1000 {
+    ///     queue.put(0).unwrap();
+    /// }
+    /// queue.put(1).unwrap();
+    /// let taken: usize = thread::scope(|s| {
+    ///     let workers: Vec<_> = (0..4)
+    ///         .map(|_| s.spawn(|| std::iter::from_fn(|| take_even(&queue)).count()))
+    ///         .collect();
+    ///     workers.into_iter().map(|worker| worker.join().unwrap()).sum()
+    /// });
+    /// assert_eq!(taken, 1000);
+    ///
+    /// let mut guard = queue.lock();
+    /// assert_eq!(guard.len(), 1);
+    /// assert_eq!(guard.get(), Some(1));
+    /// ```
+    pub fn lock(&self) -> QueueGuard<'_, Q, T> {
+        QueueGuard {
+            inner: &self.inner,
+            queue: Some(self.inner.queue.lock().unwrap()),
+            taken: false,
+            puts: 0,
+        }
+    }
+}
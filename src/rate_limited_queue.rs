@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time;
+
+use crate::queue::*;
+
+pub(crate) struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    rate: f64,
+    refilled_at: time::Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(limit: usize, per: time::Duration) -> Self {
+        assert!(limit > 0, "a rate limit must allow at least one put");
+        assert!(!per.is_zero(), "a rate limit needs a nonzero interval");
+        Self {
+            capacity: limit as f64,
+            tokens: limit as f64,
+            rate: limit as f64 / per.as_secs_f64(),
+            refilled_at: time::Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = time::Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.refilled_at = now;
+    }
+
+    pub(crate) fn try_acquire(&mut self) -> bool {
+        self.try_acquire_many(1)
+    }
+
+    /// Takes `count` tokens at once, or none if there aren't that many. More than the
+    /// limit can never be taken at once.
+    pub(crate) fn try_acquire_many(&mut self, count: usize) -> bool {
+        self.refill();
+        if self.tokens >= count as f64 {
+            self.tokens -= count as f64;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn time_until_token(&mut self) -> time::Duration {
+        self.refill();
+        if self.tokens >= 1.0 {
+            time::Duration::ZERO
+        } else {
+            time::Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+        }
+    }
+}
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Creates a queue which accepts at most `limit` puts per `per` interval.
+    ///
+    /// Puts over the limit fail with `QueueError::RateLimited`, and `put_wait` sleeps until
+    /// a token becomes available or the timeout elapses.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` or `per` is zero.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time;
+    /// use rueue::{PutError, Queue, QueueError, RateLimitedQueue};
+    ///
+    /// let queue = RateLimitedQueue::with_rate_limit(None, 3, time::Duration::from_secs(1));
+    ///
+    /// queue.put(1).unwrap();
+    /// queue.put(2).unwrap();
+    /// queue.put(3).unwrap();
+    /// assert!(matches!(queue.put(4), Err(PutError(4, QueueError::RateLimited))));
+    /// assert_eq!(queue.len(), 3);
+    ///
+    /// let queue = RateLimitedQueue::with_rate_limit(None, 1, time::Duration::from_millis(50));
+    ///
+    /// queue.put(1).unwrap();
+    /// let timestamp = time::Instant::now();
+    /// queue.put_wait(2, time::Duration::from_millis(1000)).unwrap();
+    /// assert!(timestamp.elapsed() >= time::Duration::from_millis(40));
+    /// assert!(matches!(
+    ///     queue.put_wait(3, time::Duration::from_millis(10)),
+    ///     Err(PutError(3, QueueError::RateLimited))
+    /// ));
+    /// ```
+    ///
+    /// A limit which would never let a put through is refused.
+    /// ```should_panic
+    /// use std::time;
+    /// use rueue::RateLimitedQueue;
+    ///
+    /// let _: RateLimitedQueue<i32> = RateLimitedQueue::with_rate_limit(None, 0, time::Duration::from_secs(1));
+    /// ```
+    pub fn with_rate_limit(maxsize: Option<usize>, limit: usize, per: time::Duration) -> Self {
+        let mut inner = QueueInner::new(maxsize);
+        inner.rate_limit = Some(Mutex::new(TokenBucket::new(limit, per)));
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+/// Fifo Queue limiting the rate of puts with a token bucket.
+///
+/// This is an alias of `FifoQueue`, not a distinct type: the limit comes from creating the
+/// queue with `with_rate_limit`, which every queue type has, and a `RateLimitedQueue` made
+/// with `new` is not limited at all.
+///
+/// # Example
+/// ```
+/// use std::time;
+/// use rueue::{Queue, RateLimitedQueue};
+///
+/// let queue = RateLimitedQueue::with_rate_limit(None, 1000, time::Duration::from_secs(1));
+///
+/// let accepted = (0..2000).filter(|i| queue.put(*i).is_ok()).count();
+/// assert!(accepted < 2000);
+///
+/// let first_item = queue.get().unwrap();
+/// assert_eq!(first_item, 0);
+/// ```
+pub type RateLimitedQueue<T> = BaseQueue<VecDeque<T>, T>;
@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, RwLock};
+use std::time;
+
+use crate::queue::*;
+
+struct RwInner<T> {
+    queue: RwLock<VecDeque<T>>,
+    /// The bound and the waiting state, shared with the other queues. Its own store is
+    /// left empty.
+    waits: QueueInner<VecDeque<T>, T>,
+}
+
+/// Fifo Queue behind a `RwLock` instead of a `Mutex`, for queues watched by many threads
+/// and changed by few. Operations which only look at the queue, like `len`, `peek` and
+/// `snapshot`, share the lock; only puts and gets take it exclusively.
+///
+/// # Example
+/// ```
+/// use std::sync::{Arc, Barrier};
+/// use std::thread;
+/// use std::time;
+/// use rueue::{Queue, RwQueue};
+///
+/// let queue = RwQueue::new(Some(8));
+///
+/// // Every reader holds the shared lock until all of them hold it, which an exclusive
+/// // lock would never allow.
+/// let barrier = Arc::new(Barrier::new(4));
+/// let readers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let watcher = queue.clone();
+///         let barrier = Arc::clone(&barrier);
+///         thread::spawn(move || {
+///             watcher.inspect(|items| {
+///                 barrier.wait();
+///                 items.len()
+///             })
+///         })
+///     })
+///     .collect();
+/// for reader in readers {
+///     assert_eq!(reader.join().unwrap(), 0);
+/// }
+///
+/// let writers: Vec<_> = (0..2)
+///     .map(|w| {
+///         let producer = queue.clone();
+///         thread::spawn(move || {
+///             for i in 0..500 {
+///                 producer.put_wait(w * 500 + i, time::Duration::ZERO).unwrap();
+///             }
+///         })
+///     })
+///     .collect();
+/// let readers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let watcher = queue.clone();
+///         thread::spawn(move || {
+///             for _ in 0..1000 {
+///                 assert!(watcher.len() <= 8);
+///                 assert!(watcher.snapshot().len() <= 8);
+///             }
+///         })
+///     })
+///     .collect();
+///
+/// let mut taken: Vec<_> = (0..1000)
+///     .map(|_| queue.get_wait(time::Duration::ZERO).unwrap())
+///     .collect();
+/// for th in writers.into_iter().chain(readers) {
+///     th.join().unwrap();
+/// }
+/// taken.sort();
+/// assert_eq!(taken, (0..1000).collect::<Vec<_>>());
+/// assert!(queue.is_empty());
+/// ```
+pub struct RwQueue<T> {
+    inner: Arc<RwInner<T>>,
+}
+
+impl<T> RwQueue<T> {
+    pub fn new(maxsize: Option<usize>) -> Self {
+        Self::with_waits(maxsize, QueueInner::with_array(VecDeque::new(), maxsize))
+    }
+
+    /// Creates a queue whose blocking operations give up with `QueueError::ShutDown` once
+    /// `flag` is set, like `BaseQueue::with_shutdown`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{Queue, QueueError, RwQueue};
+    ///
+    /// let shutdown = Arc::new(AtomicBool::new(false));
+    /// let queue: RwQueue<i32> = RwQueue::with_shutdown(None, Arc::clone(&shutdown));
+    /// queue.set_max_park(time::Duration::from_millis(10));
+    ///
+    /// let consumer = queue.clone();
+    /// let th = thread::spawn(move || consumer.get_wait(time::Duration::ZERO));
+    /// while queue.waiting_consumers() < 1 {
+    ///     thread::sleep(time::Duration::from_millis(1));
+    /// }
+    ///
+    /// shutdown.store(true, Ordering::SeqCst);
+    /// assert_eq!(th.join().unwrap(), Err(QueueError::ShutDown));
+    /// assert_eq!(queue.waiting_consumers(), 0);
+    /// ```
+    pub fn with_shutdown(maxsize: Option<usize>, flag: Arc<AtomicBool>) -> Self {
+        let mut waits = QueueInner::with_array(VecDeque::new(), maxsize);
+        waits.shutdown = Some(flag);
+        Self::with_waits(maxsize, waits)
+    }
+
+    fn with_waits(maxsize: Option<usize>, waits: QueueInner<VecDeque<T>, T>) -> Self {
+        Self {
+            inner: Arc::new(RwInner {
+                queue: RwLock::new(BasicArray::new(maxsize)),
+                waits,
+            }),
+        }
+    }
+
+    /// Caps how long a blocked thread sleeps before re-checking, like
+    /// `BaseQueue::set_max_park`. A zero `max_park` removes the cap.
+    pub fn set_max_park(&self, max_park: time::Duration) {
+        self.inner.waits.set_max_park(max_park);
+    }
+
+    /// Returns the number of threads blocked waiting for an item.
+    pub fn waiting_consumers(&self) -> usize {
+        self.inner.waits.waiting_consumers.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of threads blocked waiting for room to put an item.
+    pub fn waiting_producers(&self) -> usize {
+        self.inner.waits.waiting_producers.load(Ordering::SeqCst)
+    }
+
+    /// Runs `f` on the queued items, front first, under the shared lock.
+    pub fn inspect<R>(&self, f: impl FnOnce(&VecDeque<T>) -> R) -> R {
+        f(&self.inner.queue.read().unwrap())
+    }
+
+    fn is_full_at(&self, len: usize) -> bool {
+        self.inner.waits.is_full_at(len)
+    }
+
+    fn notify(&self, condvar: &Condvar) {
+        let _pending = self.inner.waits.pending.lock().unwrap();
+        condvar.notify_one();
+    }
+}
+
+impl<T: Clone> RwQueue<T> {
+    /// Returns a clone of the item `get` would take next, without taking it.
+    ///
+    /// # Example
+    /// ```
+    /// use rueue::{Queue, RwQueue};
+    ///
+    /// let queue = RwQueue::new(None);
+    /// assert_eq!(queue.peek(), None);
+    /// queue.put(1).unwrap();
+    /// queue.put(2).unwrap();
+    /// assert_eq!(queue.peek(), Some(1));
+    /// assert_eq!(queue.snapshot(), vec![1, 2]);
+    /// assert_eq!(queue.len(), 2);
+    /// ```
+    pub fn peek(&self) -> Option<T> {
+        self.inner.queue.read().unwrap().front().cloned()
+    }
+
+    /// Returns clones of the queued items, front first.
+    pub fn snapshot(&self) -> Vec<T> {
+        self.inner.queue.read().unwrap().iter().cloned().collect()
+    }
+}
+
+impl<T> Queue<T> for RwQueue<T> {
+    fn len(&self) -> usize {
+        self.inner.queue.read().unwrap().len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn is_full(&self) -> bool {
+        self.is_full_at(self.len())
+    }
+
+    fn is_bounded(&self) -> bool {
+        self.inner.waits.maxsize().is_some()
+    }
+
+    fn maxsize(&self) -> Option<usize> {
+        self.inner.waits.maxsize()
+    }
+
+    fn get(&self) -> Result<T, QueueError> {
+        let value = self.inner.queue.write().unwrap().pop_front();
+        match value {
+            Some(value) => {
+                self.notify(&self.inner.waits.not_full);
+                Ok(value)
+            }
+            None => Err(QueueError::Empty),
+        }
+    }
+
+    fn get_wait(&self, timeout: time::Duration) -> Result<T, QueueError> {
+        let timestamp = time::Instant::now();
+        loop {
+            if self.inner.waits.is_shut_down() {
+                return Err(QueueError::ShutDown);
+            }
+            if let Ok(value) = self.get() {
+                return Ok(value);
+            }
+            let remaining = match timeout.is_zero() {
+                true => time::Duration::ZERO,
+                false => match timeout.checked_sub(timestamp.elapsed()) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    _ => return Err(QueueError::Empty),
+                },
+            };
+            let _waiting = Waiting::new(&self.inner.waits.waiting_consumers);
+            let waits = &self.inner.waits;
+            if !waits.wait_until(&waits.not_empty, || !self.is_empty(), remaining)
+                && !waits.is_shut_down()
+            {
+                return Err(QueueError::Empty);
+            }
+        }
+    }
+
+    fn put(&self, value: T) -> Result<(), PutError<T>> {
+        let mut queue = self.inner.queue.write().unwrap();
+        if self.is_full_at(queue.len()) {
+            return Err(PutError(value, QueueError::Full));
+        }
+        queue.push_back(value);
+        drop(queue);
+        self.notify(&self.inner.waits.not_empty);
+        Ok(())
+    }
+
+    fn put_wait(&self, mut value: T, timeout: time::Duration) -> Result<(), PutError<T>> {
+        let timestamp = time::Instant::now();
+        loop {
+            if self.inner.waits.is_shut_down() {
+                return Err(PutError(value, QueueError::ShutDown));
+            }
+            match self.put(value) {
+                Err(PutError(rejected, QueueError::Full)) => value = rejected,
+                ret => return ret,
+            }
+            let remaining = match timeout.is_zero() {
+                true => time::Duration::ZERO,
+                false => match timeout.checked_sub(timestamp.elapsed()) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    _ => return Err(PutError(value, QueueError::Full)),
+                },
+            };
+            let _waiting = Waiting::new(&self.inner.waits.waiting_producers);
+            let waits = &self.inner.waits;
+            if !waits.wait_until(&waits.not_full, || !self.is_full(), remaining)
+                && !waits.is_shut_down()
+            {
+                return Err(PutError(value, QueueError::Full));
+            }
+        }
+    }
+}
+
+impl<T> Clone for RwQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
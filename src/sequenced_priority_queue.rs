@@ -0,0 +1,184 @@
+use std::cmp::{Ord, Ordering};
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+use crate::priority_queue::PrioritizedItem;
+use crate::queue::*;
+
+/// Which of several items of equal priority is taken first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// The item put first.
+    #[default]
+    Fifo,
+    /// The item put last.
+    Lifo,
+}
+
+struct Entry<T, P> {
+    item: PrioritizedItem<T, P>,
+    /// Requeued items are taken before any other of their priority.
+    requeued: bool,
+    /// Breaks remaining ties between equal priorities, the greater rank being taken first.
+    rank: u64,
+}
+
+impl<T, P: Ord> Eq for Entry<T, P> {}
+
+impl<T, P: Ord> PartialEq<Self> for Entry<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<T, P: Ord> PartialOrd<Self> for Entry<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, P: Ord> Ord for Entry<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.item
+            .1
+            .cmp(&other.item.1)
+            .then_with(|| self.requeued.cmp(&other.requeued))
+            .then_with(|| self.rank.cmp(&other.rank))
+    }
+}
+
+/// A heap which numbers items as they are put, and takes items of equal priority by that
+/// number, oldest or newest first.
+pub struct SequencedHeap<T, P> {
+    heap: BinaryHeap<Entry<T, P>>,
+    sequence: u64,
+    tie_break: TieBreak,
+}
+
+impl<T, P> SequencedHeap<T, P> {
+    fn with_tie_break(maxsize: Option<usize>, tie_break: TieBreak) -> Self {
+        Self {
+            heap: match maxsize {
+                None => BinaryHeap::new(),
+                Some(s) => BinaryHeap::with_capacity(s),
+            },
+            sequence: 0,
+            tie_break,
+        }
+    }
+}
+
+impl<T, P: Ord> BasicArray<PrioritizedItem<T, P>> for SequencedHeap<T, P> {
+    fn new(maxsize: Option<usize>) -> Self {
+        Self::with_tie_break(maxsize, TieBreak::default())
+    }
+
+    fn new_empty(&self, maxsize: Option<usize>) -> Self {
+        Self::with_tie_break(maxsize, self.tie_break)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn get(&mut self) -> Option<PrioritizedItem<T, P>> {
+        self.heap.pop().map(|entry| entry.item)
+    }
+
+    fn put(&mut self, value: PrioritizedItem<T, P>) {
+        // Ranking by the inverted sequence puts older items first.
+        let rank = match self.tie_break {
+            TieBreak::Fifo => !self.sequence,
+            TieBreak::Lifo => self.sequence,
+        };
+        self.sequence += 1;
+        self.heap.push(Entry {
+            item: value,
+            requeued: false,
+            rank,
+        });
+    }
+
+    fn peek(&self) -> Option<&PrioritizedItem<T, P>> {
+        self.heap.peek().map(|entry| &entry.item)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &PrioritizedItem<T, P>> + '_> {
+        Box::new(self.heap.iter().map(|entry| &entry.item))
+    }
+
+    /// A requeued item is taken before any other of its priority, the last one requeued
+    /// first.
+    fn requeue(&mut self, value: PrioritizedItem<T, P>) {
+        self.heap.push(Entry {
+            item: value,
+            requeued: true,
+            rank: self.sequence,
+        });
+        self.sequence += 1;
+    }
+
+    fn capacity(&self) -> usize {
+        self.heap.capacity()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.heap.shrink_to_fit()
+    }
+}
+
+impl<T, P: Ord> SequencedPriorityQueue<T, P> {
+    /// Creates a queue taking items of equal priority in `tie_break` order.
+    pub fn with_tie_break(maxsize: Option<usize>, tie_break: TieBreak) -> Self {
+        Self {
+            inner: Arc::new(QueueInner::with_array(
+                SequencedHeap::with_tie_break(maxsize, tie_break),
+                maxsize,
+            )),
+        }
+    }
+}
+
+/// Queue with a priority, which takes items of equal priority in the order they were put,
+/// or in the reverse order with `TieBreak::Lifo`.
+///
+/// # Example
+/// ```
+/// use rueue::{PrioritizedItem, Queue, SequencedPriorityQueue, TieBreak};
+///
+/// let fifo = SequencedPriorityQueue::new(None);
+/// let lifo = SequencedPriorityQueue::with_tie_break(None, TieBreak::Lifo);
+/// for queue in [&fifo, &lifo] {
+///     queue.put(PrioritizedItem("first", 1)).unwrap();
+///     queue.put(PrioritizedItem("second", 1)).unwrap();
+///     queue.put(PrioritizedItem("urgent", 2)).unwrap();
+///     queue.put(PrioritizedItem("third", 1)).unwrap();
+/// }
+///
+/// let taken = |queue: &SequencedPriorityQueue<_, _>| {
+///     (0..4).map(|_| queue.get().unwrap().0).collect::<Vec<_>>()
+/// };
+/// assert_eq!(taken(&fifo), vec!["urgent", "first", "second", "third"]);
+/// assert_eq!(taken(&lifo), vec!["urgent", "third", "second", "first"]);
+/// ```
+///
+/// Items given back with `get_ack` are taken again before the others of their priority,
+/// the last one given back first.
+/// ```
+/// use std::time;
+/// use rueue::{PrioritizedItem, Queue, SequencedPriorityQueue};
+///
+/// let queue = SequencedPriorityQueue::new(None);
+/// queue.put(PrioritizedItem("first", 1)).unwrap();
+/// queue.put(PrioritizedItem("second", 1)).unwrap();
+/// queue.put(PrioritizedItem("third", 1)).unwrap();
+///
+/// let first = queue.get_ack(time::Duration::ZERO).unwrap();
+/// let second = queue.get_ack(time::Duration::ZERO).unwrap();
+/// drop(second);
+/// drop(first);
+///
+/// let taken: Vec<_> = (0..3).map(|_| queue.get().unwrap().0).collect();
+/// assert_eq!(taken, vec!["first", "second", "third"]);
+/// ```
+pub type SequencedPriorityQueue<T, P> = BaseQueue<SequencedHeap<T, P>, PrioritizedItem<T, P>>;
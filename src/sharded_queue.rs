@@ -0,0 +1,214 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time;
+
+use crate::fifo_queue::FifoQueue;
+use crate::queue::*;
+use crate::queue_group::GroupSignal;
+
+struct ShardedInner<T> {
+    shards: Vec<FifoQueue<T>>,
+    /// Notified when any shard gets an item.
+    signal: Arc<GroupSignal>,
+    /// Notified when any shard may have room.
+    space: Arc<GroupSignal>,
+    next_put: AtomicUsize,
+    next_get: AtomicUsize,
+}
+
+/// A queue split into several `FifoQueue` shards, each with its own lock, so that many
+/// producers and consumers contend less than on a single queue.
+///
+/// Puts go to the shards in turn, and gets scan the shards starting from a rotating one.
+/// Items put into the same shard keep their order, but there is no global order: an item
+/// may be taken before one put earlier into another shard.
+///
+/// # Example
+/// ```
+/// use std::thread;
+/// use std::time;
+/// use rueue::{Queue, ShardedQueue};
+///
+/// let queue = ShardedQueue::new(4, Some(64));
+///
+/// let producers: Vec<_> = (0..8)
+///     .map(|p| {
+///         let producer = queue.clone();
+///         thread::spawn(move || {
+///             for i in 0..10_000 {
+///                 producer.put_wait(p * 10_000 + i, time::Duration::ZERO).unwrap();
+///             }
+///         })
+///     })
+///     .collect();
+/// let consumers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let consumer = queue.clone();
+///         thread::spawn(move || {
+///             let mut taken = Vec::new();
+///             while let Ok(value) = consumer.get_wait(time::Duration::from_millis(200)) {
+///                 taken.push(value);
+///             }
+///             taken
+///         })
+///     })
+///     .collect();
+///
+/// let timestamp = time::Instant::now();
+/// for producer in producers {
+///     producer.join().unwrap();
+/// }
+/// let mut taken: Vec<_> = consumers
+///     .into_iter()
+///     .flat_map(|consumer| consumer.join().unwrap())
+///     .collect();
+/// assert!(timestamp.elapsed() < time::Duration::from_secs(30));
+///
+/// taken.sort();
+/// assert_eq!(taken, (0..80_000).collect::<Vec<_>>());
+/// assert!(queue.is_empty());
+/// ```
+pub struct ShardedQueue<T> {
+    inner: Arc<ShardedInner<T>>,
+}
+
+impl<T> ShardedQueue<T> {
+    /// Creates a queue of `shards` shards, each holding at most `maxsize` items.
+    ///
+    /// # Panics
+    /// Panics if `shards` is zero.
+    pub fn new(shards: usize, maxsize: Option<usize>) -> Self {
+        assert!(shards > 0, "a sharded queue needs at least one shard");
+        let signal = Arc::new(GroupSignal::new());
+        let space = Arc::new(GroupSignal::new());
+        let shards = (0..shards)
+            .map(|_| {
+                let shard = FifoQueue::new(maxsize);
+                shard.inner.groups.lock().unwrap().push(Arc::clone(&signal));
+                let mut space_groups = shard.inner.space_groups.lock().unwrap();
+                space_groups.push(Arc::clone(&space));
+                drop(space_groups);
+                shard
+            })
+            .collect();
+        Self {
+            inner: Arc::new(ShardedInner {
+                shards,
+                signal,
+                space,
+                next_put: AtomicUsize::new(0),
+                next_get: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// The shards in the order to try them, starting from the one `next` points at.
+    fn rotation(&self, next: &AtomicUsize) -> impl Iterator<Item = &FifoQueue<T>> {
+        let shards = &self.inner.shards;
+        let start = next.fetch_add(1, Ordering::Relaxed) % shards.len();
+        shards[start..].iter().chain(&shards[..start])
+    }
+}
+
+impl<T> Queue<T> for ShardedQueue<T> {
+    fn len(&self) -> usize {
+        self.inner.shards.iter().map(|shard| shard.len()).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inner.shards.iter().all(|shard| shard.is_empty())
+    }
+
+    fn is_full(&self) -> bool {
+        self.inner.shards.iter().all(|shard| shard.is_full())
+    }
+
+    fn is_bounded(&self) -> bool {
+        self.inner.shards[0].is_bounded()
+    }
+
+    fn maxsize(&self) -> Option<usize> {
+        self.inner.shards.iter().map(|shard| shard.maxsize()).sum()
+    }
+
+    fn get(&self) -> Result<T, QueueError> {
+        self.rotation(&self.inner.next_get)
+            .find_map(|shard| shard.get().ok())
+            .ok_or(QueueError::Empty)
+    }
+
+    /// Scans the shards without any shared lock, and only sleeps on the signal shared by
+    /// the shards when none had an item.
+    fn get_wait(&self, timeout: time::Duration) -> Result<T, QueueError> {
+        let deadline = (!timeout.is_zero()).then(|| time::Instant::now() + timeout);
+        loop {
+            let seen = self.inner.signal.epoch();
+            if let Ok(value) = self.get() {
+                return Ok(value);
+            }
+            if !self.inner.signal.wait_since(seen, deadline) {
+                return Err(QueueError::Empty);
+            }
+        }
+    }
+
+    fn put(&self, mut value: T) -> Result<(), PutError<T>> {
+        for shard in self.rotation(&self.inner.next_put) {
+            match shard.put(value) {
+                Err(PutError(rejected, QueueError::Full)) => value = rejected,
+                ret => return ret,
+            }
+        }
+        Err(PutError(value, QueueError::Full))
+    }
+
+    /// Puts into the first shard with room, or else waits for room in any shard.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{Queue, ShardedQueue};
+    ///
+    /// let queue = ShardedQueue::new(4, Some(1));
+    /// for i in 0..4 {
+    ///     queue.put(i).unwrap();
+    /// }
+    ///
+    /// let producers: Vec<_> = (4..8)
+    ///     .map(|i| {
+    ///         let producer = queue.clone();
+    ///         thread::spawn(move || producer.put_wait(i, time::Duration::from_millis(5000)))
+    ///     })
+    ///     .collect();
+    /// let mut taken: Vec<_> = (0..8)
+    ///     .map(|_| queue.get_wait(time::Duration::from_millis(5000)).unwrap())
+    ///     .collect();
+    /// for producer in producers {
+    ///     producer.join().unwrap().unwrap();
+    /// }
+    /// taken.sort();
+    /// assert_eq!(taken, (0..8).collect::<Vec<_>>());
+    /// ```
+    fn put_wait(&self, mut value: T, timeout: time::Duration) -> Result<(), PutError<T>> {
+        let deadline = (!timeout.is_zero()).then(|| time::Instant::now() + timeout);
+        loop {
+            let seen = self.inner.space.epoch();
+            match self.put(value) {
+                Err(PutError(rejected, QueueError::Full)) => value = rejected,
+                ret => return ret,
+            }
+            if !self.inner.space.wait_since(seen, deadline) {
+                return Err(PutError(value, QueueError::Full));
+            }
+        }
+    }
+}
+
+impl<T> Clone for ShardedQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
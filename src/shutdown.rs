@@ -0,0 +1,73 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use crate::queue::*;
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Creates a queue whose blocking operations give up with `QueueError::ShutDown` once
+    /// `flag` is set. One flag can be shared by every queue of an application.
+    ///
+    /// The flag is checked each time a blocked operation wakes, so after setting it, call
+    /// `notify_shutdown` on each queue to wake its waiters at once. Without it they notice
+    /// at their next wakeup, or within `max_park` if one is set. Operations which don't
+    /// block, like `get` and `put`, are unaffected.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, PutError, Queue, QueueError};
+    ///
+    /// let shutdown = Arc::new(AtomicBool::new(false));
+    /// let empty: FifoQueue<i32> = FifoQueue::with_shutdown(None, Arc::clone(&shutdown));
+    /// let full = FifoQueue::with_shutdown(Some(1), Arc::clone(&shutdown));
+    /// full.put(0).unwrap();
+    ///
+    /// let consumers: Vec<_> = (0..2)
+    ///     .map(|_| {
+    ///         let consumer = empty.clone();
+    ///         thread::spawn(move || consumer.get_wait(time::Duration::ZERO))
+    ///     })
+    ///     .collect();
+    /// let producers: Vec<_> = (1..3)
+    ///     .map(|i| {
+    ///         let producer = full.clone();
+    ///         thread::spawn(move || producer.put_wait(i, time::Duration::ZERO))
+    ///     })
+    ///     .collect();
+    /// while empty.waiting_consumers() < 2 || full.waiting_producers() < 2 {
+    ///     thread::sleep(time::Duration::from_millis(1));
+    /// }
+    ///
+    /// shutdown.store(true, Ordering::SeqCst);
+    /// empty.notify_shutdown();
+    /// full.notify_shutdown();
+    /// for consumer in consumers {
+    ///     assert_eq!(consumer.join().unwrap(), Err(QueueError::ShutDown));
+    /// }
+    /// for producer in producers {
+    ///     assert!(matches!(producer.join().unwrap(), Err(PutError(_, QueueError::ShutDown))));
+    /// }
+    /// assert_eq!(full.get_wait(time::Duration::ZERO), Err(QueueError::ShutDown));
+    /// assert_eq!(full.get(), Ok(0));
+    /// ```
+    pub fn with_shutdown(maxsize: Option<usize>, flag: Arc<AtomicBool>) -> Self {
+        let mut inner = QueueInner::new(maxsize);
+        inner.shutdown = Some(flag);
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Wakes every thread blocked on the queue, so that they see a shutdown flag which was
+    /// just set.
+    pub fn notify_shutdown(&self) {
+        let _pending = self.inner.pending.lock().unwrap();
+        self.inner.not_empty.notify_all();
+        self.inner.not_full.notify_all();
+        self.inner.drained.notify_all();
+        self.inner.len_changed.notify_all();
+    }
+}
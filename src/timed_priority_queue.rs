@@ -0,0 +1,152 @@
+use std::cmp::{Ord, Ordering};
+use std::collections::BinaryHeap;
+use std::time;
+
+use crate::priority_queue::PrioritizedItem;
+use crate::queue::*;
+
+struct Entry<T, P> {
+    item: PrioritizedItem<T, P>,
+    put_at: time::Instant,
+}
+
+impl<T, P: Ord> Eq for Entry<T, P> {}
+
+impl<T, P: Ord> PartialEq<Self> for Entry<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.item.eq(&other.item)
+    }
+}
+
+impl<T, P: Ord> PartialOrd<Self> for Entry<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, P: Ord> Ord for Entry<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.item.cmp(&other.item)
+    }
+}
+
+/// A heap which records when each item was put.
+pub struct TimedHeap<T, P> {
+    heap: BinaryHeap<Entry<T, P>>,
+    /// When the last item taken was put, which a requeue gives back to it.
+    taken: Option<time::Instant>,
+}
+
+impl<T, P> TimedHeap<T, P> {
+    /// How long the item put longest ago has been held.
+    fn oldest_age(&self) -> Option<time::Duration> {
+        let put_at = self.heap.iter().map(|entry| entry.put_at).min()?;
+        Some(put_at.elapsed())
+    }
+}
+
+impl<T, P: Ord> BasicArray<PrioritizedItem<T, P>> for TimedHeap<T, P> {
+    fn new(maxsize: Option<usize>) -> Self {
+        Self {
+            heap: match maxsize {
+                None => BinaryHeap::new(),
+                Some(s) => BinaryHeap::with_capacity(s),
+            },
+            taken: None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn get(&mut self) -> Option<PrioritizedItem<T, P>> {
+        let entry = self.heap.pop()?;
+        self.taken = Some(entry.put_at);
+        Some(entry.item)
+    }
+
+    fn put(&mut self, value: PrioritizedItem<T, P>) {
+        self.heap.push(Entry {
+            item: value,
+            put_at: time::Instant::now(),
+        })
+    }
+
+    /// The requeued item gets back the put time of the last item taken, assuming it is
+    /// that item.
+    fn requeue(&mut self, value: PrioritizedItem<T, P>) {
+        self.heap.push(Entry {
+            item: value,
+            put_at: self.taken.take().unwrap_or_else(time::Instant::now),
+        })
+    }
+
+    fn peek(&self) -> Option<&PrioritizedItem<T, P>> {
+        self.heap.peek().map(|entry| &entry.item)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &PrioritizedItem<T, P>> + '_> {
+        Box::new(self.heap.iter().map(|entry| &entry.item))
+    }
+
+    fn capacity(&self) -> usize {
+        self.heap.capacity()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.heap.shrink_to_fit()
+    }
+}
+
+impl<T, P: Ord> TimedPriorityQueue<T, P> {
+    /// How long the longest held item has been queued, whatever its priority, or `None`
+    /// if the queue is empty. A low priority item kept waiting by a stream of higher ones
+    /// shows up here long before it is taken.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{PrioritizedItem, Queue, TimedPriorityQueue};
+    ///
+    /// let queue = TimedPriorityQueue::new(None);
+    /// assert_eq!(queue.oldest_age(), None);
+    ///
+    /// queue.put(PrioritizedItem("low", 1)).unwrap();
+    /// thread::sleep(time::Duration::from_millis(50));
+    /// queue.put(PrioritizedItem("high", 2)).unwrap();
+    /// assert!(queue.oldest_age().unwrap() >= time::Duration::from_millis(50));
+    ///
+    /// // Taking the high priority item leaves the low one, still the oldest.
+    /// assert_eq!(queue.get().unwrap().0, "high");
+    /// assert!(queue.oldest_age().unwrap() >= time::Duration::from_millis(50));
+    ///
+    /// // An item given back with `get_ack` keeps its put time.
+    /// drop(queue.get_ack(time::Duration::ZERO).unwrap());
+    /// assert!(queue.oldest_age().unwrap() >= time::Duration::from_millis(50));
+    ///
+    /// queue.get().unwrap();
+    /// queue.put(PrioritizedItem("new", 1)).unwrap();
+    /// assert!(queue.oldest_age().unwrap() < time::Duration::from_millis(50));
+    /// ```
+    pub fn oldest_age(&self) -> Option<time::Duration> {
+        self.inner.queue.lock().unwrap().oldest_age()
+    }
+}
+
+/// Queue with a priority which records when each item was put, so monitoring can spot
+/// starved items with `oldest_age`. Otherwise it works as a `PriorityQueue`.
+///
+/// # Example
+/// ```
+/// use rueue::{PrioritizedItem, Queue, TimedPriorityQueue};
+///
+/// let queue = TimedPriorityQueue::new(Some(2));
+/// queue.put(PrioritizedItem("low", 1)).unwrap();
+/// queue.put(PrioritizedItem("high", 2)).unwrap();
+///
+/// assert_eq!(queue.get().unwrap().0, "high");
+/// assert_eq!(queue.get().unwrap().0, "low");
+/// ```
+pub type TimedPriorityQueue<T, P> = BaseQueue<TimedHeap<T, P>, PrioritizedItem<T, P>>;
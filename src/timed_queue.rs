@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+use std::time;
+
+use crate::queue::*;
+
+/// A Fifo store which records when each item was put.
+pub struct Timestamped<T> {
+    items: VecDeque<(T, time::Instant)>,
+    /// When the last item taken was put, which a requeue gives back to it.
+    taken: Option<time::Instant>,
+}
+
+impl<T> Timestamped<T> {
+    /// Takes the front item along with how long it was queued.
+    fn get_timed(&mut self) -> Option<(T, time::Duration)> {
+        let (value, put_at) = self.items.pop_front()?;
+        self.taken = Some(put_at);
+        Some((value, put_at.elapsed()))
+    }
+}
+
+impl<T> BasicArray<T> for Timestamped<T> {
+    fn new(maxsize: Option<usize>) -> Self {
+        Self {
+            items: match maxsize {
+                None => VecDeque::new(),
+                Some(s) => VecDeque::with_capacity(s),
+            },
+            taken: None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn get(&mut self) -> Option<T> {
+        let (value, put_at) = self.items.pop_front()?;
+        self.taken = Some(put_at);
+        Some(value)
+    }
+
+    fn put(&mut self, value: T) {
+        self.items.push_back((value, time::Instant::now()))
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.items.front().map(|(value, _)| value)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(self.items.iter().map(|(value, _)| value))
+    }
+
+    /// The requeued item gets back the put time of the last item taken, assuming it is
+    /// that item.
+    fn requeue(&mut self, value: T) {
+        let put_at = self.taken.take().unwrap_or_else(time::Instant::now);
+        self.items.push_front((value, put_at))
+    }
+
+    fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.items.shrink_to_fit()
+    }
+}
+
+impl<T> TimedQueue<T> {
+    /// Like `get`, but also returns how long the item spent in the queue.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{Queue, QueueError, TimedQueue};
+    ///
+    /// let queue = TimedQueue::new(None);
+    /// assert_eq!(queue.get_timed().unwrap_err(), QueueError::Empty);
+    ///
+    /// queue.put(1).unwrap();
+    /// thread::sleep(time::Duration::from_millis(20));
+    ///
+    /// // An item given back with `get_ack` keeps its put time.
+    /// drop(queue.get_ack(time::Duration::ZERO).unwrap());
+    /// let (value, queued_for) = queue.get_timed().unwrap();
+    /// assert_eq!(value, 1);
+    /// assert!(queued_for >= time::Duration::from_millis(20));
+    /// ```
+    pub fn get_timed(&self) -> Result<(T, time::Duration), QueueError> {
+        let mut queue = self.inner.queue.lock().unwrap();
+        let value = queue.get_timed();
+        if value.is_some() {
+            self.inner.record_len(queue.len());
+        }
+        drop(queue);
+        match value {
+            Some(value) => {
+                self.inner.notify_not_full();
+                Ok(value)
+            }
+            None if self.inner.is_closed() => Err(QueueError::Closed),
+            None => Err(QueueError::Empty),
+        }
+    }
+
+    /// Like `get_wait`, but also returns how long the item spent in the queue. The time
+    /// spent waiting for the item to arrive is not part of it.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{Queue, TimedQueue};
+    ///
+    /// let queue = TimedQueue::new(None);
+    ///
+    /// let producer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     thread::sleep(time::Duration::from_millis(50));
+    ///     producer.put(1).unwrap();
+    /// });
+    ///
+    /// let (value, queued_for) = queue.get_timed_wait(time::Duration::from_millis(1000)).unwrap();
+    /// assert_eq!(value, 1);
+    /// assert!(queued_for < time::Duration::from_millis(50));
+    /// th.join().unwrap();
+    /// ```
+    pub fn get_timed_wait(
+        &self,
+        timeout: time::Duration,
+    ) -> Result<(T, time::Duration), QueueError> {
+        let timestamp = time::Instant::now();
+        loop {
+            if self.inner.is_shut_down() {
+                return Err(QueueError::ShutDown);
+            }
+            match self.get_timed() {
+                Err(QueueError::Empty) => {}
+                ret => return ret,
+            }
+            let remaining = match timeout.is_zero() {
+                true => time::Duration::ZERO,
+                false => match timeout.checked_sub(timestamp.elapsed()) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    _ => return Err(QueueError::Empty),
+                },
+            };
+            let _waiting = Waiting::new(&self.inner.waiting_consumers);
+            self.inner.wait_until(
+                &self.inner.not_empty,
+                || !self.is_empty() || self.inner.is_closed(),
+                remaining,
+            );
+        }
+    }
+}
+
+/// Fifo Queue which records when each item was put, so consumers can measure how long
+/// items wait with `get_timed`. The plain `get` methods work as for a `FifoQueue`.
+///
+/// # Example
+/// ```
+/// use std::thread;
+/// use std::time;
+/// use rueue::{Queue, TimedQueue};
+///
+/// let queue = TimedQueue::new(Some(2));
+/// queue.put("early").unwrap();
+/// thread::sleep(time::Duration::from_millis(20));
+/// queue.put("late").unwrap();
+///
+/// let (_, early) = queue.get_timed().unwrap();
+/// let (_, late) = queue.get_timed().unwrap();
+/// assert!(early >= time::Duration::from_millis(20));
+/// assert!(early > late);
+/// ```
+pub type TimedQueue<T> = BaseQueue<Timestamped<T>, T>;
@@ -0,0 +1,112 @@
+use std::cmp::{Ord, Ordering};
+use std::collections::BinaryHeap;
+
+use crate::priority_queue::PrioritizedItem;
+use crate::queue::*;
+
+/// A `PrioritizedItem` ordered and compared by value as well as priority, so items of
+/// equal priority but different values are not equal. Among items of equal priority, the
+/// greater value is greater. `ValueOrderedPriorityQueue` orders its items this way.
+///
+/// # Example
+/// ```
+/// use rueue::{PrioritizedItem, TotalPrioritizedItem};
+///
+/// assert!(PrioritizedItem("a", 1) == PrioritizedItem("b", 1));
+/// assert!(TotalPrioritizedItem::from(PrioritizedItem("a", 1)) != PrioritizedItem("b", 1).into());
+/// assert!(TotalPrioritizedItem::from(PrioritizedItem("a", 1)) == PrioritizedItem("a", 1).into());
+/// assert!(TotalPrioritizedItem::from(PrioritizedItem("z", 1)) < PrioritizedItem("a", 2).into());
+///
+/// let item = TotalPrioritizedItem(PrioritizedItem("a", 1));
+/// assert_eq!(item.into_inner().into_parts(), ("a", 1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TotalPrioritizedItem<T, P>(pub PrioritizedItem<T, P>);
+
+impl<T, P> TotalPrioritizedItem<T, P> {
+    pub fn into_inner(self) -> PrioritizedItem<T, P> {
+        self.0
+    }
+}
+
+impl<T, P> From<PrioritizedItem<T, P>> for TotalPrioritizedItem<T, P> {
+    fn from(item: PrioritizedItem<T, P>) -> Self {
+        Self(item)
+    }
+}
+
+impl<T: Ord, P: Ord> Eq for TotalPrioritizedItem<T, P> {}
+
+impl<T: Ord, P: Ord> PartialEq<Self> for TotalPrioritizedItem<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<T: Ord, P: Ord> PartialOrd<Self> for TotalPrioritizedItem<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord, P: Ord> Ord for TotalPrioritizedItem<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0)
+            .1
+            .cmp(&(other.0).1)
+            .then_with(|| (self.0).0.cmp(&(other.0).0))
+    }
+}
+
+pub struct ValueOrderedHeap<T, P>(BinaryHeap<TotalPrioritizedItem<T, P>>);
+
+impl<T: Ord, P: Ord> BasicArray<PrioritizedItem<T, P>> for ValueOrderedHeap<T, P> {
+    fn new(maxsize: Option<usize>) -> Self {
+        match maxsize {
+            None => Self(BinaryHeap::new()),
+            Some(s) => Self(BinaryHeap::with_capacity(s)),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get(&mut self) -> Option<PrioritizedItem<T, P>> {
+        self.0.pop().map(|item| item.0)
+    }
+
+    fn put(&mut self, value: PrioritizedItem<T, P>) {
+        self.0.push(TotalPrioritizedItem(value))
+    }
+
+    fn peek(&self) -> Option<&PrioritizedItem<T, P>> {
+        self.0.peek().map(|item| &item.0)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &PrioritizedItem<T, P>> + '_> {
+        Box::new(self.0.iter().map(|item| &item.0))
+    }
+}
+
+/// Queue with a priority, where items of equal priority are taken greatest value first.
+///
+/// Ties are broken by the value alone, never by insertion order.
+///
+/// # Example
+/// ```
+/// use rueue::{PrioritizedItem, Queue, ValueOrderedPriorityQueue};
+///
+/// let queue = ValueOrderedPriorityQueue::new(None);
+///
+/// queue.put(PrioritizedItem(1, 5)).unwrap();
+/// queue.put(PrioritizedItem(3, 5)).unwrap();
+/// queue.put(PrioritizedItem(7, 1)).unwrap();
+/// queue.put(PrioritizedItem(2, 5)).unwrap();
+///
+/// assert_eq!(queue.get().unwrap().0, 3);
+/// assert_eq!(queue.get().unwrap().0, 2);
+/// assert_eq!(queue.get().unwrap().0, 1);
+/// assert_eq!(queue.get().unwrap().0, 7);
+/// ```
+pub type ValueOrderedPriorityQueue<T, P> = BaseQueue<ValueOrderedHeap<T, P>, PrioritizedItem<T, P>>;
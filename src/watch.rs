@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use std::time;
+
+use crate::queue::*;
+
+/// Follows the length of a queue, made by `BaseQueue::watch`.
+pub struct LengthWatcher<Q, T> {
+    inner: Arc<QueueInner<Q, T>>,
+    last: usize,
+}
+
+impl<Q: BasicArray<T>, T> LengthWatcher<Q, T> {
+    fn len(&self) -> usize {
+        self.inner.queue.lock().unwrap().len()
+    }
+
+    /// The length last returned by `changed`, or the length when the watcher was made.
+    pub fn last(&self) -> usize {
+        self.last
+    }
+
+    /// Blocks until the length differs from the last one observed, and returns the new
+    /// length. A zero `timeout` waits without limit.
+    ///
+    /// Returns `None` if the length is still unchanged when `timeout` elapses, when the
+    /// queue is closed, or when it is shut down.
+    ///
+    /// Changes which cancel out between two calls, like a put followed by a get, may go
+    /// unseen.
+    pub fn changed(&mut self, timeout: time::Duration) -> Option<usize> {
+        self.inner.wait_until(
+            &self.inner.len_changed,
+            || self.len() != self.last || self.inner.is_closed(),
+            timeout,
+        );
+        let len = self.len();
+        if len == self.last {
+            return None;
+        }
+        self.last = len;
+        Some(len)
+    }
+}
+
+impl<Q: BasicArray<T>, T> BaseQueue<Q, T> {
+    /// Returns a watcher for changes to the length of the queue, starting from its
+    /// current length.
+    ///
+    /// # Example
+    /// ```
+    /// use std::thread;
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(None);
+    /// let mut watcher = queue.watch();
+    /// assert_eq!(watcher.changed(time::Duration::from_millis(10)), None);
+    ///
+    /// let producer = queue.clone();
+    /// let th = thread::spawn(move || {
+    ///     thread::sleep(time::Duration::from_millis(50));
+    ///     producer.put(1).unwrap();
+    /// });
+    /// assert_eq!(watcher.changed(time::Duration::ZERO), Some(1));
+    /// th.join().unwrap();
+    ///
+    /// queue.get().unwrap();
+    /// assert_eq!(watcher.changed(time::Duration::ZERO), Some(0));
+    /// assert_eq!(watcher.last(), 0);
+    /// ```
+    pub fn watch(&self) -> LengthWatcher<Q, T> {
+        LengthWatcher {
+            inner: Arc::clone(&self.inner),
+            last: self.len(),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+use std::thread;
+use std::time;
+
+use crate::queue::*;
+
+/// The worker threads started by `process_with`.
+pub struct WorkerHandle {
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl WorkerHandle {
+    /// How many workers were started.
+    pub fn len(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.workers.is_empty()
+    }
+
+    /// Waits for every worker to exit, which they do once the queue is closed and drained.
+    /// Returns the panic of the first worker which panicked, after all have exited.
+    pub fn join(self) -> thread::Result<()> {
+        let mut ret = Ok(());
+        for worker in self.workers {
+            if let Err(panic) = worker.join() {
+                ret = ret.and(Err(panic));
+            }
+        }
+        ret
+    }
+}
+
+impl<Q: BasicArray<T> + Send + 'static, T: Send + Sync + 'static> BaseQueue<Q, T> {
+    /// Starts `workers` threads which each take items with `get_wait` and call `f` on
+    /// them, until the queue is closed and drained or shut down.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time;
+    /// use rueue::{FifoQueue, Queue};
+    ///
+    /// let queue = FifoQueue::new(Some(8));
+    /// let handled = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let sink = Arc::clone(&handled);
+    /// let workers = queue.process_with(4, move |i: u64| sink.lock().unwrap().push(i * i));
+    /// assert_eq!(workers.len(), 4);
+    /// for i in 0..100 {
+    ///     queue.put_wait(i, time::Duration::ZERO).unwrap();
+    /// }
+    /// queue.close();
+    /// workers.join().unwrap();
+    ///
+    /// let mut handled = handled.lock().unwrap().clone();
+    /// handled.sort();
+    /// assert_eq!(handled, (0..100).map(|i| i * i).collect::<Vec<_>>());
+    /// ```
+    pub fn process_with<F: Fn(T) + Send + Sync + 'static>(
+        &self,
+        workers: usize,
+        f: F,
+    ) -> WorkerHandle {
+        let f = Arc::new(f);
+        let workers = (0..workers)
+            .map(|_| {
+                let queue = self.clone();
+                let f = Arc::clone(&f);
+                thread::spawn(move || {
+                    while let Ok(value) = queue.get_wait(time::Duration::ZERO) {
+                        f(value);
+                    }
+                })
+            })
+            .collect();
+        WorkerHandle { workers }
+    }
+}